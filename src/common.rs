@@ -1,12 +1,14 @@
 use chrono::prelude::*;
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeMap;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::ops::Neg;
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum OrderType {
+    #[default]
     Market,
     Limit {
         limit_price: Decimal,
@@ -24,8 +26,9 @@ pub enum OrderType {
     },
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub enum TimeInForce {
+    #[default]
     #[serde(rename = "day")]
     Day,
     #[serde(rename = "gtc")]
@@ -40,12 +43,6 @@ pub enum TimeInForce {
     FillOrKill,
 }
 
-impl Default for TimeInForce {
-    fn default() -> Self {
-        TimeInForce::Day
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct TakeProfitSpec {
     pub limit_price: Decimal,
@@ -57,9 +54,10 @@ pub struct StopLossSpec {
     pub limit_price: Decimal,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderClass {
+    #[default]
     Simple,
     Bracket {
         take_profit: TakeProfitSpec,
@@ -74,13 +72,7 @@ pub enum OrderClass {
     },
 }
 
-impl Default for OrderClass {
-    fn default() -> Self {
-        OrderClass::Simple
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum OrderStatus {
     Accepted,
@@ -90,6 +82,7 @@ pub enum OrderStatus {
     DoneForDay,
     Expired,
     Filled,
+    #[default]
     New,
     PartiallyFilled,
     PendingCancel,
@@ -101,25 +94,43 @@ pub enum OrderStatus {
     Suspended,
 }
 
-impl Default for OrderStatus {
-    fn default() -> OrderStatus {
-        OrderStatus::New
+impl OrderStatus {
+    /// Whether the order has reached a final state and will not change anymore.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled
+                | OrderStatus::Canceled
+                | OrderStatus::Expired
+                | OrderStatus::Replaced
+                | OrderStatus::Rejected
+        )
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.is_terminal()
+    }
+
+    pub fn can_cancel(&self) -> bool {
+        self.is_open() && *self != OrderStatus::PendingCancel
+    }
+
+    pub fn can_replace(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Accepted | OrderStatus::New | OrderStatus::PartiallyFilled
+        )
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum Side {
+    #[default]
     Buy,
     Sell,
 }
 
-impl Default for Side {
-    fn default() -> Side {
-        Side::Buy
-    }
-}
-
 impl Neg for Side {
     type Output = Side;
 
@@ -131,6 +142,100 @@ impl Neg for Side {
     }
 }
 
+/// The size of an order, either as a (possibly fractional) number of shares or as a dollar
+/// amount. Alpaca treats `qty` and `notional` as mutually exclusive.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Quantity {
+    Qty(Decimal),
+    Notional(Decimal),
+}
+
+impl Quantity {
+    pub fn qty(&self) -> Option<Decimal> {
+        match self {
+            Quantity::Qty(qty) => Some(*qty),
+            Quantity::Notional(_) => None,
+        }
+    }
+
+    pub fn notional(&self) -> Option<Decimal> {
+        match self {
+            Quantity::Qty(_) => None,
+            Quantity::Notional(notional) => Some(*notional),
+        }
+    }
+}
+
+impl Default for Quantity {
+    fn default() -> Self {
+        Quantity::Qty(Decimal::ONE)
+    }
+}
+
+impl Serialize for Quantity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self {
+            Quantity::Qty(qty) => map.serialize_entry("qty", qty)?,
+            Quantity::Notional(notional) => map.serialize_entry("notional", notional)?,
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D>(deserializer: D) -> Result<Quantity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawQuantity {
+            #[serde(default)]
+            qty: Option<Decimal>,
+            #[serde(default)]
+            notional: Option<Decimal>,
+        }
+
+        let raw = RawQuantity::deserialize(deserializer)?;
+        match (raw.qty, raw.notional) {
+            (Some(qty), None) => Ok(Quantity::Qty(qty)),
+            (None, Some(notional)) => Ok(Quantity::Notional(notional)),
+            (Some(_), Some(_)) => Err(de::Error::custom(
+                "`qty` and `notional` are mutually exclusive",
+            )),
+            (None, None) => Err(de::Error::custom("missing `qty` or `notional`")),
+        }
+    }
+}
+
+/// The body of a single item of a bulk request, such as cancelling all orders or closing all
+/// positions: the resulting order on success, and the error otherwise.
+#[cfg(feature = "rest")]
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum OrderResponse {
+    Order(Order),
+    Error(crate::errors::ApiError),
+}
+
+#[cfg(feature = "rest")]
+impl OrderResponse {
+    /// Bulk responses carry the status of each item next to its body, so an error's status has to
+    /// be filled in afterwards.
+    pub(crate) fn with_status(mut self, status: u16) -> Self {
+        if let (OrderResponse::Error(e), Ok(status)) =
+            (&mut self, vila::StatusCode::from_u16(status))
+        {
+            e.status = status;
+        }
+        self
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Order {
     pub id: Uuid,
@@ -148,16 +253,9 @@ pub struct Order {
     pub asset_id: Uuid,
     pub symbol: String,
     pub asset_class: String,
-    #[serde(
-        deserialize_with = "crate::utils::from_str",
-        serialize_with = "crate::utils::to_string"
-    )]
-    pub qty: usize,
-    #[serde(
-        deserialize_with = "crate::utils::from_str",
-        serialize_with = "crate::utils::to_string"
-    )]
-    pub filled_qty: usize,
+    #[serde(flatten)]
+    pub quantity: Quantity,
+    pub filled_qty: Decimal,
     pub filled_avg_price: Option<Decimal>,
     #[serde(flatten, rename(serialize = "type"))]
     pub order_type: OrderType,
@@ -168,3 +266,134 @@ pub struct Order {
     pub legs: Option<Vec<Order>>,
     pub hwm: Option<Decimal>,
 }
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AccountStatus {
+    Onboarding,
+    SubmissionFailed,
+    Submitted,
+    AccountUpdate,
+    ApprovalPending,
+    Active,
+    Rejected,
+}
+
+/// The account fields pushed on the `account_updates` stream.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct AccountUpdate {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub status: AccountStatus,
+    pub currency: String,
+    pub cash: Decimal,
+    pub cash_withdrawable: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Trade {
+    #[serde(rename = "t")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "x")]
+    pub exchange: String,
+    #[serde(rename = "p")]
+    pub price: Decimal,
+    #[serde(rename = "s")]
+    pub size: u64,
+    #[serde(rename = "c", default)]
+    pub conditions: Vec<String>,
+    #[serde(rename = "i")]
+    pub id: u64,
+    #[serde(rename = "z")]
+    pub tape: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Quote {
+    #[serde(rename = "t")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "ax")]
+    pub ask_exchange: String,
+    #[serde(rename = "ap")]
+    pub ask_price: Decimal,
+    #[serde(rename = "as")]
+    pub ask_size: u64,
+    #[serde(rename = "bx")]
+    pub bid_exchange: String,
+    #[serde(rename = "bp")]
+    pub bid_price: Decimal,
+    #[serde(rename = "bs")]
+    pub bid_size: u64,
+    #[serde(rename = "c", default)]
+    pub conditions: Vec<String>,
+    #[serde(rename = "z")]
+    pub tape: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bar {
+    #[serde(rename = "t")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "o")]
+    pub open: Decimal,
+    #[serde(rename = "h")]
+    pub high: Decimal,
+    #[serde(rename = "l")]
+    pub low: Decimal,
+    #[serde(rename = "c")]
+    pub close: Decimal,
+    #[serde(rename = "v")]
+    pub volume: u64,
+    #[serde(rename = "n", default)]
+    pub trade_count: Option<u64>,
+    #[serde(rename = "vw", default)]
+    pub vwap: Option<Decimal>,
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    /// An open market order to buy 100 shares of AAPL.
+    #[cfg(any(feature = "rest", feature = "ws"))]
+    pub(crate) const ORDER: &str = r#"{
+        "id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+        "client_order_id": "eb9e2aaa-f71a-4f51-b5b4-52a6c565dad4",
+        "created_at": "2021-03-16T18:38:01.942282Z",
+        "updated_at": "2021-03-16T18:38:01.942282Z",
+        "submitted_at": "2021-03-16T18:38:01.937734Z",
+        "filled_at": null,
+        "expired_at": null,
+        "canceled_at": null,
+        "failed_at": null,
+        "replaced_at": null,
+        "replaced_by": null,
+        "replaces": null,
+        "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+        "symbol": "AAPL",
+        "asset_class": "us_equity",
+        "qty": "100",
+        "filled_qty": "0",
+        "filled_avg_price": null,
+        "order_class": "",
+        "type": "market",
+        "side": "buy",
+        "time_in_force": "day",
+        "status": "new",
+        "extended_hours": false,
+        "legs": null,
+        "hwm": null
+    }"#;
+
+    /// `ORDER` with the given id, which is also used as its `client_order_id`, created and
+    /// submitted at `submitted_at`.
+    #[cfg(feature = "rest")]
+    pub(crate) fn order_json(id: &str, submitted_at: &str) -> String {
+        let mut order: serde_json::Value = serde_json::from_str(ORDER).unwrap();
+        order["id"] = id.into();
+        order["client_order_id"] = id.into();
+        order["created_at"] = submitted_at.into();
+        order["submitted_at"] = submitted_at.into();
+        order.to_string()
+    }
+}
@@ -1,5 +1,14 @@
 use thiserror::Error;
 
+#[cfg(feature = "rest")]
+use rust_decimal::Decimal;
+#[cfg(feature = "rest")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "rest")]
+use uuid::Uuid;
+#[cfg(feature = "rest")]
+use vila::StatusCode;
+
 #[cfg(feature = "ws")]
 use tokio_tungstenite::tungstenite;
 
@@ -10,7 +19,11 @@ pub enum Error {
 
     #[cfg(feature = "rest")]
     #[error(transparent)]
-    Vila(#[from] vila::Error),
+    Vila(vila::Error),
+
+    #[cfg(feature = "rest")]
+    #[error(transparent)]
+    Api(#[from] ApiError),
 
     #[error("Serde error: {0}")]
     Serde(#[from] serde_json::Error),
@@ -30,6 +43,252 @@ pub enum Error {
     #[cfg(feature = "ws")]
     #[error("Failed to connect: {0}")]
     ConnectionFailure(String),
+
+    #[cfg(feature = "ws")]
+    #[error("WebSocket closed by server with code {code}: {reason}")]
+    ConnectionClosed { code: u16, reason: String },
+
+    #[cfg(feature = "ws")]
+    #[error("No frame received within {0:?}")]
+    StaleConnection(std::time::Duration),
+
+    #[cfg(feature = "ws")]
+    #[error("Requested streams {requested:?} but server is listening to {actual:?}")]
+    SubscriptionMismatch {
+        requested: Vec<crate::stream::StreamName>,
+        actual: Vec<crate::stream::StreamName>,
+    },
+
+    #[cfg(feature = "ws")]
+    #[error("Market data stream error {code}: {message}")]
+    MarketData { code: u32, message: String },
+
+    #[cfg(feature = "ws")]
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "ws")]
+    #[error("Subscriber {subscriber} lagged behind and skipped {skipped} messages")]
+    Lagged { subscriber: String, skipped: u64 },
+}
+
+#[cfg(feature = "rest")]
+impl Error {
+    /// Classifies failed requests by their HTTP status, whether or not the response body could
+    /// be decoded. Returns `None` for errors that did not come with a response.
+    pub fn kind(&self) -> Option<ApiErrorKind> {
+        match self {
+            Error::Api(e) => Some(e.kind()),
+            Error::Vila(vila::Error::ClientError(status, _))
+            | Error::Vila(vila::Error::ServerError(status, _)) => Some((*status).into()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "rest")]
+impl From<vila::Error> for Error {
+    /// Decodes the body of failed requests into an [`ApiError`], falling back to the raw
+    /// `vila::Error` if it is not one of Alpaca's JSON error bodies.
+    fn from(e: vila::Error) -> Self {
+        let api_error = match &e {
+            vila::Error::ClientError(status, body) | vila::Error::ServerError(status, body) => {
+                ApiError::from_response(*status, body)
+            }
+            _ => None,
+        };
+        api_error.map_or(Error::Vila(e), Error::Api)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// The error codes Alpaca documents. Any other code is kept as `Other`.
+#[cfg(feature = "rest")]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(from = "u32", into = "u32")]
+pub enum ApiErrorCode {
+    MalformedRequest,
+    Unauthorized,
+    /// E.g. insufficient buying power or a potential wash trade.
+    Forbidden,
+    NotFound,
+    Unprocessable,
+    RateLimited,
+    InternalServerError,
+    Other(u32),
+}
+
+#[cfg(feature = "rest")]
+impl From<u32> for ApiErrorCode {
+    fn from(code: u32) -> Self {
+        match code {
+            40010000 => ApiErrorCode::MalformedRequest,
+            40110000 => ApiErrorCode::Unauthorized,
+            40310000 => ApiErrorCode::Forbidden,
+            40410000 => ApiErrorCode::NotFound,
+            42210000 => ApiErrorCode::Unprocessable,
+            42910000 => ApiErrorCode::RateLimited,
+            50010000 => ApiErrorCode::InternalServerError,
+            other => ApiErrorCode::Other(other),
+        }
+    }
+}
+
+#[cfg(feature = "rest")]
+impl From<ApiErrorCode> for u32 {
+    fn from(code: ApiErrorCode) -> Self {
+        match code {
+            ApiErrorCode::MalformedRequest => 40010000,
+            ApiErrorCode::Unauthorized => 40110000,
+            ApiErrorCode::Forbidden => 40310000,
+            ApiErrorCode::NotFound => 40410000,
+            ApiErrorCode::Unprocessable => 42210000,
+            ApiErrorCode::RateLimited => 42910000,
+            ApiErrorCode::InternalServerError => 50010000,
+            ApiErrorCode::Other(code) => code,
+        }
+    }
+}
+
+/// A classification of failed requests by their HTTP status.
+#[cfg(feature = "rest")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiErrorKind {
+    Auth,
+    Forbidden,
+    NotFound,
+    Unprocessable,
+    RateLimited,
+    Server,
+    Other,
+}
+
+#[cfg(feature = "rest")]
+impl From<StatusCode> for ApiErrorKind {
+    fn from(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ApiErrorKind::Auth,
+            StatusCode::FORBIDDEN => ApiErrorKind::Forbidden,
+            StatusCode::NOT_FOUND => ApiErrorKind::NotFound,
+            StatusCode::UNPROCESSABLE_ENTITY => ApiErrorKind::Unprocessable,
+            StatusCode::TOO_MANY_REQUESTS => ApiErrorKind::RateLimited,
+            status if status.is_server_error() => ApiErrorKind::Server,
+            _ => ApiErrorKind::Other,
+        }
+    }
+}
+
+/// An error returned by the Alpaca API, decoded from the response body.
+#[cfg(feature = "rest")]
+#[derive(Error, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[error("Alpaca API error {} ({status}): {message}", u32::from(*.code))]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub code: ApiErrorCode,
+    pub message: String,
+    pub buying_power: Option<Decimal>,
+    pub cost_basis: Option<Decimal>,
+    #[serde(default)]
+    pub related_orders: Vec<Uuid>,
+}
+
+#[cfg(feature = "rest")]
+impl ApiError {
+    /// Returns `None` if `body` is not a JSON error body.
+    pub fn from_response(status: StatusCode, body: &str) -> Option<Self> {
+        let mut error: Self = serde_json::from_str(body).ok()?;
+        error.status = status;
+        Some(error)
+    }
+
+    pub fn kind(&self) -> ApiErrorKind {
+        self.status.into()
+    }
+}
+
+#[cfg(all(test, feature = "rest"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_api_error() {
+        let e = Error::from(vila::Error::ClientError(
+            StatusCode::FORBIDDEN,
+            r#"{
+                "code": 40310000,
+                "message": "insufficient buying power",
+                "buying_power": "1500.25",
+                "cost_basis": "2000"
+            }"#
+            .into(),
+        ));
+        let api = match e {
+            Error::Api(api) => api,
+            e => panic!("Expected an API error, got {:?}", e),
+        };
+        assert_eq!(api.kind(), ApiErrorKind::Forbidden);
+        assert_eq!(api.code, ApiErrorCode::Forbidden);
+        assert_eq!(api.buying_power, Some(Decimal::new(150025, 2)));
+        assert_eq!(api.cost_basis, Some(Decimal::new(2000, 0)));
+        assert!(api.related_orders.is_empty());
+        assert_eq!(
+            api.to_string(),
+            "Alpaca API error 40310000 (403 Forbidden): insufficient buying power"
+        );
+
+        let e = Error::from(vila::Error::ClientError(
+            StatusCode::FORBIDDEN,
+            r#"{
+                "code": 40310100,
+                "message": "potential wash trade detected. use complex orders",
+                "related_orders": ["904837e3-3b76-47ec-b432-046db621571b"]
+            }"#
+            .into(),
+        ));
+        assert!(matches!(
+            e,
+            Error::Api(ApiError {
+                code: ApiErrorCode::Other(40310100),
+                ref related_orders,
+                ..
+            }) if related_orders.len() == 1
+        ));
+    }
+
+    #[test]
+    fn classify_status() {
+        let kind = |status| {
+            ApiError::from_response(status, r#"{"code":50010000,"message":"internal error"}"#)
+                .unwrap()
+                .kind()
+        };
+        assert_eq!(kind(StatusCode::UNAUTHORIZED), ApiErrorKind::Auth);
+        assert_eq!(kind(StatusCode::NOT_FOUND), ApiErrorKind::NotFound);
+        assert_eq!(
+            kind(StatusCode::TOO_MANY_REQUESTS),
+            ApiErrorKind::RateLimited
+        );
+        assert_eq!(kind(StatusCode::BAD_GATEWAY), ApiErrorKind::Server);
+        assert_eq!(kind(StatusCode::BAD_REQUEST), ApiErrorKind::Other);
+    }
+
+    #[test]
+    fn undecodable_body() {
+        let e = Error::from(vila::Error::ServerError(
+            StatusCode::BAD_GATEWAY,
+            "<html>Bad Gateway</html>".into(),
+        ));
+        assert!(matches!(e, Error::Vila(vila::Error::ServerError(..))));
+        assert_eq!(e.kind(), Some(ApiErrorKind::Server));
+
+        let e = Error::from(vila::Error::ClientError(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too Many Requests".into(),
+        ));
+        assert_eq!(e.kind(), Some(ApiErrorKind::RateLimited));
+        let e = Error::from(serde_json::from_str::<u32>("").unwrap_err());
+        assert_eq!(e.kind(), None);
+    }
+}
@@ -1,14 +1,23 @@
 pub mod common;
 pub mod errors;
 #[cfg(feature = "rest")]
+pub mod market_data;
+#[cfg(feature = "rest")]
 pub mod rest;
 #[cfg(feature = "ws")]
 pub mod stream;
+#[cfg(all(feature = "rest", feature = "ws"))]
+pub mod tracking;
+#[cfg(feature = "rest")]
 mod utils;
 
 pub use common::*;
 pub use errors::*;
 #[cfg(feature = "rest")]
+pub use market_data::*;
+#[cfg(feature = "rest")]
 pub use rest::*;
 #[cfg(feature = "ws")]
 pub use stream::*;
+#[cfg(all(feature = "rest", feature = "ws"))]
+pub use tracking::*;
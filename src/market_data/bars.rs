@@ -0,0 +1,310 @@
+use crate::market_data::{page_token_paginator, Adjustment, Feed, Page, TimeFrame};
+use crate::rest::PageToken;
+use crate::utils::{null_as_default, to_comma_separated, to_string};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use vila::pagination::{query::QueryPaginator, PaginatedRequest};
+use vila::{Request, RequestData};
+
+pub use crate::common::Bar;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BarsPage {
+    #[serde(deserialize_with = "null_as_default")]
+    pub bars: Vec<Bar>,
+    pub symbol: String,
+    pub next_page_token: Option<String>,
+}
+
+impl Page for BarsPage {
+    type Item = Bar;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Bar> {
+        self.bars
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MultiBarsPage {
+    #[serde(deserialize_with = "null_as_default")]
+    pub bars: BTreeMap<String, Vec<Bar>>,
+    pub next_page_token: Option<String>,
+}
+
+impl Page for MultiBarsPage {
+    type Item = (String, Bar);
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<(String, Bar)> {
+        self.bars
+            .into_iter()
+            .flat_map(|(symbol, bars)| bars.into_iter().map(move |bar| (symbol.clone(), bar)))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetBars<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(serialize_with = "to_string")]
+    pub timeframe: TimeFrame,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<Adjustment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl<'a> GetBars<'a> {
+    pub fn new(symbol: &'a str, timeframe: TimeFrame) -> Self {
+        Self {
+            symbol,
+            timeframe,
+            start: None,
+            end: None,
+            limit: None,
+            adjustment: None,
+            feed: None,
+            page_token: None,
+        }
+    }
+
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn adjustment(mut self, adjustment: Adjustment) -> Self {
+        self.adjustment = Some(adjustment);
+        self
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+
+impl Request for GetBars<'_> {
+    type Data = Self;
+    type Response = BarsPage;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("stocks/{}/bars", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetBars<'_> {
+    type Data = PageToken;
+    type Paginator = QueryPaginator<BarsPage, PageToken>;
+
+    fn initial_page(&self) -> Option<PageToken> {
+        self.page_token.clone().map(PageToken)
+    }
+
+    fn paginator(&self) -> Self::Paginator {
+        page_token_paginator()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetMultiBars<'a> {
+    #[serde(serialize_with = "to_comma_separated")]
+    pub symbols: Vec<&'a str>,
+    #[serde(serialize_with = "to_string")]
+    pub timeframe: TimeFrame,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<Adjustment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl<'a> GetMultiBars<'a> {
+    pub fn new(symbols: Vec<&'a str>, timeframe: TimeFrame) -> Self {
+        Self {
+            symbols,
+            timeframe,
+            start: None,
+            end: None,
+            limit: None,
+            adjustment: None,
+            feed: None,
+            page_token: None,
+        }
+    }
+
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn adjustment(mut self, adjustment: Adjustment) -> Self {
+        self.adjustment = Some(adjustment);
+        self
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+
+impl Request for GetMultiBars<'_> {
+    type Data = Self;
+    type Response = MultiBarsPage;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "stocks/bars".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetMultiBars<'_> {
+    type Data = PageToken;
+    type Paginator = QueryPaginator<MultiBarsPage, PageToken>;
+
+    fn initial_page(&self) -> Option<PageToken> {
+        self.page_token.clone().map(PageToken)
+    }
+
+    fn paginator(&self) -> Self::Paginator {
+        page_token_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use crate::market_data::stream_data;
+    use futures::TryStreamExt;
+    use mockito::{mock, Matcher};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_stream_bars() {
+        let _first = mock("GET", "/stocks/AAPL/bars")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::Exact(
+                "timeframe=15Min&start=2021-02-01T16%3A00%3A00Z&feed=iex".into(),
+            ))
+            .with_body(
+                r#"{
+                    "bars": [{"t": "2021-02-01T16:00:00Z", "o": 133.32, "h": 133.74, "l": 133.31, "c": 133.5, "v": 9876, "n": 120, "vw": 133.52}],
+                    "symbol": "AAPL",
+                    "next_page_token": "QUFQTHxNfDIwMjEtMDItMDFUMTY6MDA6MDAuMDAwMDAwMDAwWg=="
+                }"#,
+            )
+            .create();
+        let _second = mock("GET", "/stocks/AAPL/bars")
+            .match_query(Matcher::UrlEncoded(
+                "page_token".into(),
+                "QUFQTHxNfDIwMjEtMDItMDFUMTY6MDA6MDAuMDAwMDAwMDAwWg==".into(),
+            ))
+            .with_body(
+                r#"{
+                    "bars": [{"t": "2021-02-01T16:15:00Z", "o": 133.5, "h": 133.58, "l": 133.44, "c": 133.58, "v": 3567}],
+                    "symbol": "AAPL",
+                    "next_page_token": null
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetBars::new("AAPL", TimeFrame::Minutes(15))
+            .start("2021-02-01T16:00:00Z".parse().unwrap())
+            .feed(Feed::Iex);
+        let bars: Vec<Bar> = stream_data(&client, &request).try_collect().await.unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].close, Decimal::new(1335, 1));
+        assert_eq!(bars[1].trade_count, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_multi_bars() {
+        let _m = mock("GET", "/stocks/bars")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("symbols".into(), "AAPL,MSFT".into()),
+                Matcher::UrlEncoded("timeframe".into(), "1Day".into()),
+                Matcher::UrlEncoded("adjustment".into(), "split".into()),
+            ]))
+            .with_body(
+                r#"{
+                    "bars": {
+                        "AAPL": [{"t": "2021-02-01T05:00:00Z", "o": 133.75, "h": 135.38, "l": 130.93, "c": 134.14, "v": 106239823}],
+                        "MSFT": [{"t": "2021-02-01T05:00:00Z", "o": 235.06, "h": 242.5, "l": 232.43, "c": 239.65, "v": 33314225}]
+                    },
+                    "next_page_token": null
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetMultiBars::new(vec!["AAPL", "MSFT"], TimeFrame::Days(1))
+            .adjustment(Adjustment::Split);
+        let page = client.send(&request).await.unwrap();
+        let symbols: Vec<String> = page
+            .into_items()
+            .into_iter()
+            .map(|(symbol, _)| symbol)
+            .collect();
+        assert_eq!(symbols, vec!["AAPL", "MSFT"]);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::market_data::bars::Bar;
+use crate::market_data::quotes::Quote;
+use crate::market_data::trades::Trade;
+use crate::market_data::Feed;
+use crate::utils::to_comma_separated;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use vila::{Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LatestTrade {
+    pub symbol: String,
+    pub trade: Trade,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MultiLatestTrades {
+    pub trades: BTreeMap<String, Trade>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LatestQuote {
+    pub symbol: String,
+    pub quote: Quote,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MultiLatestQuotes {
+    pub quotes: BTreeMap<String, Quote>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LatestBar {
+    pub symbol: String,
+    pub bar: Bar,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MultiLatestBars {
+    pub bars: BTreeMap<String, Bar>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetLatestTrade<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetLatestTrade<'a> {
+    pub fn new(symbol: &'a str) -> Self {
+        Self { symbol, feed: None }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetLatestTrade<'_> {
+    type Data = Self;
+    type Response = LatestTrade;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("stocks/{}/trades/latest", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetMultiLatestTrades<'a> {
+    #[serde(serialize_with = "to_comma_separated")]
+    pub symbols: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetMultiLatestTrades<'a> {
+    pub fn new(symbols: Vec<&'a str>) -> Self {
+        Self {
+            symbols,
+            feed: None,
+        }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetMultiLatestTrades<'_> {
+    type Data = Self;
+    type Response = MultiLatestTrades;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "stocks/trades/latest".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetLatestQuote<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetLatestQuote<'a> {
+    pub fn new(symbol: &'a str) -> Self {
+        Self { symbol, feed: None }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetLatestQuote<'_> {
+    type Data = Self;
+    type Response = LatestQuote;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("stocks/{}/quotes/latest", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetMultiLatestQuotes<'a> {
+    #[serde(serialize_with = "to_comma_separated")]
+    pub symbols: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetMultiLatestQuotes<'a> {
+    pub fn new(symbols: Vec<&'a str>) -> Self {
+        Self {
+            symbols,
+            feed: None,
+        }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetMultiLatestQuotes<'_> {
+    type Data = Self;
+    type Response = MultiLatestQuotes;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "stocks/quotes/latest".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetLatestBar<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetLatestBar<'a> {
+    pub fn new(symbol: &'a str) -> Self {
+        Self { symbol, feed: None }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetLatestBar<'_> {
+    type Data = Self;
+    type Response = LatestBar;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("stocks/{}/bars/latest", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetMultiLatestBars<'a> {
+    #[serde(serialize_with = "to_comma_separated")]
+    pub symbols: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetMultiLatestBars<'a> {
+    pub fn new(symbols: Vec<&'a str>) -> Self {
+        Self {
+            symbols,
+            feed: None,
+        }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetMultiLatestBars<'_> {
+    type Data = Self;
+    type Response = MultiLatestBars;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "stocks/bars/latest".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use mockito::{mock, Matcher};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_get_latest_quote() {
+        let _m = mock("GET", "/stocks/AAPL/quotes/latest")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::UrlEncoded("feed".into(), "iex".into()))
+            .with_body(
+                r#"{
+                    "symbol": "AAPL",
+                    "quote": {"t": "2021-05-13T14:24:48.434983424Z", "ax": "V", "ap": 124.87, "as": 1, "bx": "V", "bp": 124.82, "bs": 3, "c": ["R"], "z": "C"}
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let latest = client
+            .send(&GetLatestQuote::new("AAPL").feed(Feed::Iex))
+            .await
+            .unwrap();
+        assert_eq!(latest.quote.ask_price, Decimal::new(12487, 2));
+    }
+
+    #[tokio::test]
+    async fn test_get_multi_latest_trades() {
+        let _m = mock("GET", "/stocks/trades/latest")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::UrlEncoded("symbols".into(), "AAPL,MSFT".into()))
+            .with_body(
+                r#"{
+                    "trades": {
+                        "AAPL": {"t": "2021-05-13T14:24:48.434983424Z", "x": "V", "p": 124.86, "s": 100, "c": ["@"], "i": 6104, "z": "C"},
+                        "MSFT": {"t": "2021-05-13T14:24:47.963123456Z", "x": "V", "p": 243.36, "s": 50, "c": ["@"], "i": 5011, "z": "C"}
+                    }
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let latest = client
+            .send(&GetMultiLatestTrades::new(vec!["AAPL", "MSFT"]))
+            .await
+            .unwrap();
+        assert_eq!(latest.trades["MSFT"].size, 50);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::errors::{Error, Result};
+use crate::rest::PageToken;
+use futures::{stream, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use vila::pagination::{query::QueryPaginator, PaginatedRequest};
+use vila::Client;
+
+pub mod bars;
+pub mod latest;
+pub mod quotes;
+pub mod snapshots;
+pub mod trades;
+
+/// A client for the market data API. It uses the same key pair as the paper and live trading
+/// clients.
+pub fn data_client(key: &str, secret: &str) -> Client {
+    Client::new("https://data.alpaca.markets/v2").header_auth(vec![
+        ("apca-api-key-id", key),
+        ("apca-api-secret-key", secret),
+    ])
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Feed {
+    Iex,
+    Sip,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Adjustment {
+    Raw,
+    Split,
+    Dividend,
+    All,
+}
+
+/// The aggregation period of a bar, e.g. `TimeFrame::Minutes(5)` for `5Min`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeFrame {
+    Minutes(u32),
+    Hours(u32),
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+}
+
+impl fmt::Display for TimeFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeFrame::Minutes(n) => write!(f, "{}Min", n),
+            TimeFrame::Hours(n) => write!(f, "{}Hour", n),
+            TimeFrame::Days(n) => write!(f, "{}Day", n),
+            TimeFrame::Weeks(n) => write!(f, "{}Week", n),
+            TimeFrame::Months(n) => write!(f, "{}Month", n),
+        }
+    }
+}
+
+/// A page of market data returned by one of the `next_page_token`-paginated endpoints.
+pub trait Page {
+    type Item;
+
+    fn next_page_token(&self) -> Option<&str>;
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+pub(crate) fn page_token_paginator<T: Page>() -> QueryPaginator<T, PageToken> {
+    QueryPaginator::new(|_, page: &T| {
+        page.next_page_token()
+            .map(|token| PageToken(token.to_string()))
+    })
+}
+
+/// Streams every item of a paginated market data request, following `next_page_token` until
+/// the last page.
+pub fn stream_data<'a, R>(
+    client: &'a Client,
+    request: &'a R,
+) -> impl Stream<Item = Result<<R::Response as Page>::Item>> + Unpin + 'a
+where
+    R: PaginatedRequest,
+    R::Response: Page,
+{
+    client
+        .send_paginated(request)
+        .map_err(Error::from)
+        .map_ok(|page| stream::iter(page.into_items().into_iter().map(Ok)))
+        .try_flatten()
+}
@@ -0,0 +1,236 @@
+use crate::market_data::{page_token_paginator, Feed, Page};
+use crate::rest::PageToken;
+use crate::utils::{null_as_default, to_comma_separated};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use vila::pagination::{query::QueryPaginator, PaginatedRequest};
+use vila::{Request, RequestData};
+
+pub use crate::common::Quote;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuotesPage {
+    #[serde(deserialize_with = "null_as_default")]
+    pub quotes: Vec<Quote>,
+    pub symbol: String,
+    pub next_page_token: Option<String>,
+}
+
+impl Page for QuotesPage {
+    type Item = Quote;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Quote> {
+        self.quotes
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MultiQuotesPage {
+    #[serde(deserialize_with = "null_as_default")]
+    pub quotes: BTreeMap<String, Vec<Quote>>,
+    pub next_page_token: Option<String>,
+}
+
+impl Page for MultiQuotesPage {
+    type Item = (String, Quote);
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<(String, Quote)> {
+        self.quotes
+            .into_iter()
+            .flat_map(|(symbol, quotes)| {
+                quotes.into_iter().map(move |quote| (symbol.clone(), quote))
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetQuotes<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl<'a> GetQuotes<'a> {
+    pub fn new(symbol: &'a str) -> Self {
+        Self {
+            symbol,
+            start: None,
+            end: None,
+            limit: None,
+            feed: None,
+            page_token: None,
+        }
+    }
+
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+
+impl Request for GetQuotes<'_> {
+    type Data = Self;
+    type Response = QuotesPage;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("stocks/{}/quotes", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetQuotes<'_> {
+    type Data = PageToken;
+    type Paginator = QueryPaginator<QuotesPage, PageToken>;
+
+    fn initial_page(&self) -> Option<PageToken> {
+        self.page_token.clone().map(PageToken)
+    }
+
+    fn paginator(&self) -> Self::Paginator {
+        page_token_paginator()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetMultiQuotes<'a> {
+    #[serde(serialize_with = "to_comma_separated")]
+    pub symbols: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl<'a> GetMultiQuotes<'a> {
+    pub fn new(symbols: Vec<&'a str>) -> Self {
+        Self {
+            symbols,
+            start: None,
+            end: None,
+            limit: None,
+            feed: None,
+            page_token: None,
+        }
+    }
+
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+
+impl Request for GetMultiQuotes<'_> {
+    type Data = Self;
+    type Response = MultiQuotesPage;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "stocks/quotes".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetMultiQuotes<'_> {
+    type Data = PageToken;
+    type Paginator = QueryPaginator<MultiQuotesPage, PageToken>;
+
+    fn initial_page(&self) -> Option<PageToken> {
+        self.page_token.clone().map(PageToken)
+    }
+
+    fn paginator(&self) -> Self::Paginator {
+        page_token_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use mockito::mock;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_get_quotes() {
+        let _m = mock("GET", "/stocks/AAPL/quotes")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .with_body(
+                r#"{
+                    "quotes": [
+                        {"t": "2021-02-06T13:04:56.334320128Z", "ax": "C", "ap": 387.7, "as": 1, "bx": "N", "bp": 387.6, "bs": 4, "c": ["R"], "z": "C"}
+                    ],
+                    "symbol": "AAPL",
+                    "next_page_token": null
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let page = client.send(&GetQuotes::new("AAPL")).await.unwrap();
+        assert_eq!(page.quotes[0].ask_size, 1);
+        assert_eq!(page.quotes[0].bid_price, Decimal::new(3876, 1));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::market_data::bars::Bar;
+use crate::market_data::quotes::Quote;
+use crate::market_data::trades::Trade;
+use crate::market_data::Feed;
+use crate::utils::to_comma_separated;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use vila::{Request, RequestData};
+
+/// The latest trade, quote and bars of a symbol. Any part may be missing, e.g. for a symbol that
+/// has not traded yet on the requested feed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    #[serde(default)]
+    pub latest_trade: Option<Trade>,
+    #[serde(default)]
+    pub latest_quote: Option<Quote>,
+    #[serde(default)]
+    pub minute_bar: Option<Bar>,
+    #[serde(default)]
+    pub daily_bar: Option<Bar>,
+    #[serde(default)]
+    pub prev_daily_bar: Option<Bar>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetSnapshot<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetSnapshot<'a> {
+    pub fn new(symbol: &'a str) -> Self {
+        Self { symbol, feed: None }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetSnapshot<'_> {
+    type Data = Self;
+    type Response = Snapshot;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("stocks/{}/snapshot", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetMultiSnapshots<'a> {
+    #[serde(serialize_with = "to_comma_separated")]
+    pub symbols: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+}
+impl<'a> GetMultiSnapshots<'a> {
+    pub fn new(symbols: Vec<&'a str>) -> Self {
+        Self {
+            symbols,
+            feed: None,
+        }
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+impl Request for GetMultiSnapshots<'_> {
+    type Data = Self;
+    type Response = BTreeMap<String, Snapshot>;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "stocks/snapshots".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use mockito::{mock, Matcher};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_get_snapshot() {
+        let _m = mock("GET", "/stocks/AAPL/snapshot")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .with_body(
+                r#"{
+                    "symbol": "AAPL",
+                    "latestTrade": {"t": "2021-05-11T20:00:00.435997104Z", "x": "Q", "p": 125.91, "s": 5589631, "c": ["@", "M"], "i": 179430, "z": "C"},
+                    "latestQuote": {"t": "2021-05-11T21:00:00.000000000Z", "ax": "P", "ap": 125.89, "as": 1, "bx": "P", "bp": 125.87, "bs": 6, "c": ["R"], "z": "C"},
+                    "minuteBar": {"t": "2021-05-11T19:59:00Z", "o": 125.97, "h": 126.03, "l": 125.86, "c": 125.91, "v": 520961},
+                    "dailyBar": {"t": "2021-05-11T04:00:00Z", "o": 123.5, "h": 126.27, "l": 122.77, "c": 125.91, "v": 125863164},
+                    "prevDailyBar": null
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let snapshot = client.send(&GetSnapshot::new("AAPL")).await.unwrap();
+        let quote = snapshot.latest_quote.unwrap();
+        assert_eq!(quote.bid_price, Decimal::new(12587, 2));
+        assert_eq!(snapshot.daily_bar.unwrap().volume, 125863164);
+        assert_eq!(snapshot.prev_daily_bar, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_multi_snapshots() {
+        let _m = mock("GET", "/stocks/snapshots")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("symbols".into(), "AAPL,MSFT".into()),
+                Matcher::UrlEncoded("feed".into(), "sip".into()),
+            ]))
+            .with_body(
+                r#"{
+                    "AAPL": {"latestTrade": {"t": "2021-05-11T20:00:00.435997104Z", "x": "Q", "p": 125.91, "s": 5589631, "c": ["@"], "i": 179430, "z": "C"}},
+                    "MSFT": {"latestTrade": {"t": "2021-05-11T20:00:00.123456789Z", "x": "Q", "p": 246.23, "s": 1200, "c": ["@"], "i": 93811, "z": "C"}}
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let snapshots = client
+            .send(&GetMultiSnapshots::new(vec!["AAPL", "MSFT"]).feed(Feed::Sip))
+            .await
+            .unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(
+            snapshots["MSFT"].latest_trade.as_ref().unwrap().price,
+            Decimal::new(24623, 2)
+        );
+        assert_eq!(snapshots["MSFT"].latest_quote, None);
+    }
+}
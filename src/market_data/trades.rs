@@ -0,0 +1,261 @@
+use crate::market_data::{page_token_paginator, Feed, Page};
+use crate::rest::PageToken;
+use crate::utils::{null_as_default, to_comma_separated};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use vila::pagination::{query::QueryPaginator, PaginatedRequest};
+use vila::{Request, RequestData};
+
+pub use crate::common::Trade;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TradesPage {
+    #[serde(deserialize_with = "null_as_default")]
+    pub trades: Vec<Trade>,
+    pub symbol: String,
+    pub next_page_token: Option<String>,
+}
+
+impl Page for TradesPage {
+    type Item = Trade;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Trade> {
+        self.trades
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MultiTradesPage {
+    #[serde(deserialize_with = "null_as_default")]
+    pub trades: BTreeMap<String, Vec<Trade>>,
+    pub next_page_token: Option<String>,
+}
+
+impl Page for MultiTradesPage {
+    type Item = (String, Trade);
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<(String, Trade)> {
+        self.trades
+            .into_iter()
+            .flat_map(|(symbol, trades)| {
+                trades.into_iter().map(move |trade| (symbol.clone(), trade))
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetTrades<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl<'a> GetTrades<'a> {
+    pub fn new(symbol: &'a str) -> Self {
+        Self {
+            symbol,
+            start: None,
+            end: None,
+            limit: None,
+            feed: None,
+            page_token: None,
+        }
+    }
+
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+
+impl Request for GetTrades<'_> {
+    type Data = Self;
+    type Response = TradesPage;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("stocks/{}/trades", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetTrades<'_> {
+    type Data = PageToken;
+    type Paginator = QueryPaginator<TradesPage, PageToken>;
+
+    fn initial_page(&self) -> Option<PageToken> {
+        self.page_token.clone().map(PageToken)
+    }
+
+    fn paginator(&self) -> Self::Paginator {
+        page_token_paginator()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GetMultiTrades<'a> {
+    #[serde(serialize_with = "to_comma_separated")]
+    pub symbols: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl<'a> GetMultiTrades<'a> {
+    pub fn new(symbols: Vec<&'a str>) -> Self {
+        Self {
+            symbols,
+            start: None,
+            end: None,
+            limit: None,
+            feed: None,
+            page_token: None,
+        }
+    }
+
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn feed(mut self, feed: Feed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+}
+
+impl Request for GetMultiTrades<'_> {
+    type Data = Self;
+    type Response = MultiTradesPage;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "stocks/trades".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetMultiTrades<'_> {
+    type Data = PageToken;
+    type Paginator = QueryPaginator<MultiTradesPage, PageToken>;
+
+    fn initial_page(&self) -> Option<PageToken> {
+        self.page_token.clone().map(PageToken)
+    }
+
+    fn paginator(&self) -> Self::Paginator {
+        page_token_paginator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use crate::market_data::stream_data;
+    use futures::TryStreamExt;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn test_stream_multi_trades() {
+        let _first = mock("GET", "/stocks/trades")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::Exact("symbols=AAPL%2CMSFT&limit=2&feed=sip".into()))
+            .with_body(
+                r#"{
+                    "trades": {
+                        "AAPL": [
+                            {"t": "2021-02-06T13:04:56.334320128Z", "x": "C", "p": 387.62, "s": 100, "c": [" ", "T"], "i": 52983525029461, "z": "B"},
+                            {"t": "2021-02-06T13:09:42.325484032Z", "x": "C", "p": 387.69, "s": 100, "c": [" ", "T"], "i": 52983525033813, "z": "B"}
+                        ]
+                    },
+                    "next_page_token": "QUFQTHwyMDIxLTAyLTA2VDEzOjA5OjQyLjMyNTQ4NDAzMlp8QzUyOTgzNTI1MDMzODEz"
+                }"#,
+            )
+            .create();
+        let _second = mock("GET", "/stocks/trades")
+            .match_query(Matcher::UrlEncoded(
+                "page_token".into(),
+                "QUFQTHwyMDIxLTAyLTA2VDEzOjA5OjQyLjMyNTQ4NDAzMlp8QzUyOTgzNTI1MDMzODEz".into(),
+            ))
+            .with_body(
+                r#"{
+                    "trades": {
+                        "MSFT": [
+                            {"t": "2021-02-06T13:35:08.946977536Z", "x": "P", "p": 242.7, "s": 1, "i": 52983525037164, "z": "C"}
+                        ]
+                    },
+                    "next_page_token": null
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetMultiTrades::new(vec!["AAPL", "MSFT"])
+            .limit(2)
+            .feed(Feed::Sip);
+        let trades: Vec<(String, Trade)> =
+            stream_data(&client, &request).try_collect().await.unwrap();
+        assert_eq!(trades.len(), 3);
+        assert_eq!(trades[2].0, "MSFT");
+        assert!(trades[2].1.conditions.is_empty());
+    }
+}
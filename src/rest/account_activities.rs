@@ -1,69 +1,547 @@
+use crate::errors::{Error, Result};
+use crate::rest::orders::Sort;
+use crate::rest::PageToken;
 use crate::utils::*;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::{stream, Stream, TryStreamExt};
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 use std::borrow::Cow;
+use std::fmt;
 use uuid::Uuid;
-use vila::Request;
+use vila::pagination::{query::QueryPaginator, PaginatedRequest};
+use vila::{Client, Request, RequestData};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// The kind of an account activity, as given by Alpaca's `activity_type` code. Codes this crate
+/// doesn't know about yet are kept in `Unknown` rather than failing deserialization.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ActivityType {
+    Fill,
+    Transaction,
+    Miscellaneous,
+    AcatsInOutCash,
+    AcatsInOutSecurities,
+    CashDeposit,
+    CashWithdrawal,
+    Dividend,
+    DividendCapitalGainLongTerm,
+    DividendCapitalGainShortTerm,
+    DividendFee,
+    DividendAdjusted,
+    DividendNraWithheld,
+    DividendReturnOfCapital,
+    DividendTefraWithheld,
+    DividendTaxExempt,
+    Interest,
+    InterestNraWithheld,
+    InterestTefraWithheld,
+    Journal,
+    JournalCash,
+    JournalStock,
+    MergerAcquisition,
+    NameChange,
+    OptionAssignment,
+    OptionExpiration,
+    OptionExercise,
+    PassThruCharge,
+    PassThruRebate,
+    Reorg,
+    SymbolChange,
+    StockSpinoff,
+    StockSplit,
+    Split,
+    Fee,
+    CryptoFee,
+    Unknown(String),
+}
+
+impl ActivityType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ActivityType::Fill => "FILL",
+            ActivityType::Transaction => "TRANS",
+            ActivityType::Miscellaneous => "MISC",
+            ActivityType::AcatsInOutCash => "ACATC",
+            ActivityType::AcatsInOutSecurities => "ACATS",
+            ActivityType::CashDeposit => "CSD",
+            ActivityType::CashWithdrawal => "CSW",
+            ActivityType::Dividend => "DIV",
+            ActivityType::DividendCapitalGainLongTerm => "DIVCGL",
+            ActivityType::DividendCapitalGainShortTerm => "DIVCGS",
+            ActivityType::DividendFee => "DIVFEE",
+            ActivityType::DividendAdjusted => "DIVFT",
+            ActivityType::DividendNraWithheld => "DIVNRA",
+            ActivityType::DividendReturnOfCapital => "DIVROC",
+            ActivityType::DividendTefraWithheld => "DIVTW",
+            ActivityType::DividendTaxExempt => "DIVTXEX",
+            ActivityType::Interest => "INT",
+            ActivityType::InterestNraWithheld => "INTNRA",
+            ActivityType::InterestTefraWithheld => "INTTW",
+            ActivityType::Journal => "JNL",
+            ActivityType::JournalCash => "JNLC",
+            ActivityType::JournalStock => "JNLS",
+            ActivityType::MergerAcquisition => "MA",
+            ActivityType::NameChange => "NC",
+            ActivityType::OptionAssignment => "OPASN",
+            ActivityType::OptionExpiration => "OPEXP",
+            ActivityType::OptionExercise => "OPXRC",
+            ActivityType::PassThruCharge => "PTC",
+            ActivityType::PassThruRebate => "PTR",
+            ActivityType::Reorg => "REORG",
+            ActivityType::SymbolChange => "SC",
+            ActivityType::StockSpinoff => "SSO",
+            ActivityType::StockSplit => "SSP",
+            ActivityType::Split => "SPLIT",
+            ActivityType::Fee => "FEE",
+            ActivityType::CryptoFee => "CFEE",
+            ActivityType::Unknown(code) => code,
+        }
+    }
+}
+
+impl From<&str> for ActivityType {
+    fn from(code: &str) -> Self {
+        match code {
+            "FILL" => ActivityType::Fill,
+            "TRANS" => ActivityType::Transaction,
+            "MISC" => ActivityType::Miscellaneous,
+            "ACATC" => ActivityType::AcatsInOutCash,
+            "ACATS" => ActivityType::AcatsInOutSecurities,
+            "CSD" => ActivityType::CashDeposit,
+            "CSW" => ActivityType::CashWithdrawal,
+            "DIV" => ActivityType::Dividend,
+            "DIVCGL" => ActivityType::DividendCapitalGainLongTerm,
+            "DIVCGS" => ActivityType::DividendCapitalGainShortTerm,
+            "DIVFEE" => ActivityType::DividendFee,
+            "DIVFT" => ActivityType::DividendAdjusted,
+            "DIVNRA" => ActivityType::DividendNraWithheld,
+            "DIVROC" => ActivityType::DividendReturnOfCapital,
+            "DIVTW" => ActivityType::DividendTefraWithheld,
+            "DIVTXEX" => ActivityType::DividendTaxExempt,
+            "INT" => ActivityType::Interest,
+            "INTNRA" => ActivityType::InterestNraWithheld,
+            "INTTW" => ActivityType::InterestTefraWithheld,
+            "JNL" => ActivityType::Journal,
+            "JNLC" => ActivityType::JournalCash,
+            "JNLS" => ActivityType::JournalStock,
+            "MA" => ActivityType::MergerAcquisition,
+            "NC" => ActivityType::NameChange,
+            "OPASN" => ActivityType::OptionAssignment,
+            "OPEXP" => ActivityType::OptionExpiration,
+            "OPXRC" => ActivityType::OptionExercise,
+            "PTC" => ActivityType::PassThruCharge,
+            "PTR" => ActivityType::PassThruRebate,
+            "REORG" => ActivityType::Reorg,
+            "SC" => ActivityType::SymbolChange,
+            "SSO" => ActivityType::StockSpinoff,
+            "SSP" => ActivityType::StockSplit,
+            "SPLIT" => ActivityType::Split,
+            "FEE" => ActivityType::Fee,
+            "CFEE" => ActivityType::CryptoFee,
+            other => ActivityType::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ActivityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ActivityType {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ActivityType {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<ActivityType, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let code = String::deserialize(deserializer)?;
+        Ok(ActivityType::from(code.as_str()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum FillType {
     Fill,
     PartialFill,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename = "snake_case")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum Side {
     Buy,
     Sell,
     SellShort,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TradeActivity {
+    pub activity_type: ActivityType,
+    pub id: String,
+    pub qty: Decimal,
+    pub cum_qty: Decimal,
+    pub leaves_qty: Decimal,
+    pub price: Decimal,
+    pub side: Side,
+    pub symbol: String,
+    pub transaction_time: DateTime<Utc>,
+    pub order_id: Uuid,
+    #[serde(rename = "type")]
+    pub fill_type: FillType,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NonTradeActivity {
+    pub activity_type: ActivityType,
+    pub id: String,
+    pub date: NaiveDate,
+    #[serde(deserialize_with = "from_str", serialize_with = "to_string")]
+    pub net_amount: Decimal,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "from_str_optional",
+        serialize_with = "to_string_optional"
+    )]
+    pub qty: Option<Decimal>,
+    #[serde(default)]
+    pub per_share_amount: Option<Decimal>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// An account activity. `activity_type` decides the shape: `FILL` activities are trades and
+/// every other code is a non-trade activity.
+#[derive(Serialize, Clone, Debug, PartialEq)]
 #[serde(untagged)]
 pub enum Activity {
-    TradeActivity {
-        activity_type: String,
-        id: String,
-        #[serde(deserialize_with = "from_str", serialize_with = "to_string")]
-        qty: i32,
-        #[serde(deserialize_with = "from_str", serialize_with = "to_string")]
-        cum_qty: i32,
-        #[serde(deserialize_with = "from_str", serialize_with = "to_string")]
-        leaves_qty: i32,
-        price: Decimal,
-        side: Side,
-        symbol: String,
-        transaction_time: DateTime<Utc>,
-        order_id: Uuid,
-        #[serde(rename(serialize = "type", deserialize = "type"))]
-        fill_type: FillType,
-    },
-    NonTradeActivity {
-        activity_type: String,
-        id: String,
-        date: DateTime<Utc>,
-        #[serde(deserialize_with = "from_str", serialize_with = "to_string")]
-        net_amount: Decimal,
-        symbol: Option<String>,
-        #[serde(
-            deserialize_with = "from_str_optional",
-            serialize_with = "to_string_optional"
-        )]
-        qty: Option<i32>,
-        per_share_amount: Option<Decimal>,
-    },
-}
-
-#[derive(Clone, Debug)]
-pub struct GetAccountActivities;
+    Trade(TradeActivity),
+    NonTrade(NonTradeActivity),
+}
+
+impl<'de> Deserialize<'de> for Activity {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Activity, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        let activity_type = value
+            .get("activity_type")
+            .ok_or_else(|| de::Error::missing_field("activity_type"))
+            .and_then(|code| ActivityType::deserialize(code).map_err(de::Error::custom))?;
+        match activity_type {
+            ActivityType::Fill => TradeActivity::deserialize(value)
+                .map(Activity::Trade)
+                .map_err(de::Error::custom),
+            _ => NonTradeActivity::deserialize(value)
+                .map(Activity::NonTrade)
+                .map_err(de::Error::custom),
+        }
+    }
+}
+
+impl Activity {
+    pub fn id(&self) -> &str {
+        match self {
+            Activity::Trade(activity) => &activity.id,
+            Activity::NonTrade(activity) => &activity.id,
+        }
+    }
+
+    pub fn activity_type(&self) -> &ActivityType {
+        match self {
+            Activity::Trade(activity) => &activity.activity_type,
+            Activity::NonTrade(activity) => &activity.activity_type,
+        }
+    }
+}
+
+/// The largest `page_size` Alpaca honours, which is also its default.
+const MAX_PAGE_SIZE: u16 = 100;
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct GetAccountActivities {
+    /// Restricts the request to a single activity type via `account/activities/{type}`.
+    #[serde(skip)]
+    pub activity_type: Option<ActivityType>,
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "to_comma_separated"
+    )]
+    pub activity_types: Vec<ActivityType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Sort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl GetAccountActivities {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn activity_type(mut self, activity_type: ActivityType) -> Self {
+        self.activity_type = Some(activity_type);
+        self
+    }
+
+    pub fn activity_types(mut self, activity_types: Vec<ActivityType>) -> Self {
+        self.activity_types = activity_types;
+        self
+    }
+
+    pub fn date(mut self, date: NaiveDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    pub fn after(mut self, after: DateTime<Utc>) -> Self {
+        self.after = Some(after);
+        self
+    }
+
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn direction(mut self, direction: Sort) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Sets the number of activities per page, capped at Alpaca's maximum of 100.
+    pub fn page_size(mut self, page_size: u16) -> Self {
+        self.page_size = Some(page_size.min(MAX_PAGE_SIZE));
+        self
+    }
+
+    pub fn page_token(mut self, page_token: String) -> Self {
+        self.page_token = Some(page_token);
+        self
+    }
+}
+
 impl Request for GetAccountActivities {
-    type Data = ();
+    type Data = Self;
     type Response = Vec<Activity>;
 
-    fn endpoint(&self) -> Cow<str> {
-        "account/activities".into()
+    fn endpoint(&self) -> Cow<'_, str> {
+        match &self.activity_type {
+            Some(activity_type) => format!("account/activities/{}", activity_type).into(),
+            None => "account/activities".into(),
+        }
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+impl PaginatedRequest for GetAccountActivities {
+    type Data = PageToken;
+    type Paginator = QueryPaginator<Vec<Activity>, PageToken>;
+
+    fn initial_page(&self) -> Option<PageToken> {
+        self.page_token.clone().map(PageToken)
+    }
+
+    fn paginator(&self) -> Self::Paginator {
+        let page_size = self.page_size.unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE) as usize;
+        QueryPaginator::new(move |_, activities: &Vec<Activity>| {
+            if activities.len() < page_size {
+                None
+            } else {
+                activities
+                    .last()
+                    .map(|activity| PageToken(activity.id().to_string()))
+            }
+        })
+    }
+}
+
+/// Streams the full account history matching `request`, following `page_token` until a short
+/// page is returned.
+pub fn stream_account_activities<'a>(
+    client: &'a Client,
+    request: &'a GetAccountActivities,
+) -> impl Stream<Item = Result<Activity>> + Unpin + 'a {
+    client
+        .send_paginated(request)
+        .map_err(Error::from)
+        .map_ok(|activities| stream::iter(activities.into_iter().map(Ok)))
+        .try_flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use mockito::{mock, Matcher};
+
+    fn dividend(id: &str) -> String {
+        format!(
+            r#"{{
+                "activity_type": "DIV",
+                "id": "{}",
+                "date": "2019-08-01",
+                "net_amount": "1.02",
+                "symbol": "T",
+                "qty": "2",
+                "per_share_amount": "0.51"
+            }}"#,
+            id
+        )
+    }
+
+    #[test]
+    fn serde_activities() {
+        let json = r#"[
+            {
+                "activity_type": "FILL",
+                "cum_qty": "1",
+                "id": "20190524113406977::8efc7b9a-8b2b-4000-9955-d36e7db0df74",
+                "leaves_qty": "0",
+                "price": "1.63",
+                "qty": "1",
+                "side": "sell_short",
+                "symbol": "LPCN",
+                "transaction_time": "2019-05-24T15:34:06.977Z",
+                "order_id": "904837e3-3b76-47ec-b432-046db621571b",
+                "type": "fill"
+            },
+            {
+                "activity_type": "FEE",
+                "id": "20190802011955195::5f596936-6f23-4cef-bdf1-3806aae57dbf",
+                "date": "2019-08-02",
+                "net_amount": "-0.02",
+                "description": "REG fee",
+                "status": "executed"
+            },
+            {
+                "activity_type": "NEWCODE",
+                "id": "20190803011955195::5f596936-6f23-4cef-bdf1-3806aae57dbf",
+                "date": "2019-08-03",
+                "net_amount": "0"
+            }
+        ]"#;
+        let activities: Vec<Activity> = serde_json::from_str(json).unwrap();
+        match &activities[0] {
+            Activity::Trade(trade) => assert_eq!(trade.side, Side::SellShort),
+            _ => panic!(),
+        }
+        match &activities[1] {
+            Activity::NonTrade(fee) => {
+                assert_eq!(fee.activity_type, ActivityType::Fee);
+                assert_eq!(fee.net_amount, Decimal::new(-2, 2));
+            }
+            _ => panic!(),
+        }
+        assert_eq!(
+            activities[2].activity_type(),
+            &ActivityType::Unknown("NEWCODE".into())
+        );
+
+        let serialized = serde_json::to_value(&activities[0]).unwrap();
+        assert_eq!(serialized["activity_type"], "FILL");
+        assert_eq!(serialized["type"], "fill");
+
+        let malformed_fill = r#"{
+            "activity_type": "FILL",
+            "id": "20190801011955195::5f596936-6f23-4cef-bdf1-3806aae57dbf",
+            "date": "2019-08-01",
+            "net_amount": "1.02"
+        }"#;
+        assert!(serde_json::from_str::<Activity>(malformed_fill).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_account_activities_by_type() {
+        let _m = mock("GET", "/account/activities/DIV")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("date".into(), "2019-08-01".into()),
+                Matcher::UrlEncoded("direction".into(), "asc".into()),
+            ]))
+            .with_body(format!("[{}]", dividend("20190801011955195::5f596936")))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetAccountActivities::new()
+            .activity_type(ActivityType::Dividend)
+            .date(NaiveDate::from_ymd_opt(2019, 8, 1).unwrap())
+            .direction(Sort::Ascending);
+        client.send(&request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stream_account_activities() {
+        let _first = mock("GET", "/account/activities")
+            .match_query(Matcher::Exact(
+                "activity_types=DIV%2CFEE&page_size=2".into(),
+            ))
+            .with_body(format!("[{},{}]", dividend("1"), dividend("2")))
+            .create();
+        let _second = mock("GET", "/account/activities")
+            .match_query(Matcher::UrlEncoded("page_token".into(), "2".into()))
+            .with_body(format!("[{}]", dividend("3")))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetAccountActivities::new()
+            .activity_types(vec![ActivityType::Dividend, ActivityType::Fee])
+            .page_size(2);
+        let ids: Vec<String> = stream_account_activities(&client, &request)
+            .map_ok(|activity| activity.id().to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_account_activities_capped_page_size() {
+        let ids: Vec<String> = (1..=101).map(|id| id.to_string()).collect();
+        let full_page: Vec<String> = ids[..100].iter().map(|id| dividend(id)).collect();
+        let _first = mock("GET", "/account/activities")
+            .match_query(Matcher::Exact("page_size=100".into()))
+            .with_body(format!("[{}]", full_page.join(",")))
+            .create();
+        let _second = mock("GET", "/account/activities")
+            .match_query(Matcher::UrlEncoded("page_token".into(), "100".into()))
+            .with_body(format!("[{}]", dividend("101")))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetAccountActivities::new().page_size(500);
+        let streamed: Vec<String> = stream_account_activities(&client, &request)
+            .map_ok(|activity| activity.id().to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(streamed, ids);
     }
 }
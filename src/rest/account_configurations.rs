@@ -2,31 +2,23 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use vila::{Method, Request, RequestData};
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum DtbpCheck {
     Both,
+    #[default]
     Entry,
     Exit,
 }
-impl Default for DtbpCheck {
-    fn default() -> Self {
-        Self::Entry
-    }
-}
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum TradeConfirmEmail {
+    #[default]
     All,
     #[serde(rename = "none")]
     Zero,
 }
-impl Default for TradeConfirmEmail {
-    fn default() -> Self {
-        Self::All
-    }
-}
 
 #[derive(Deserialize, Serialize, Default, Clone, Debug)]
 pub struct AccountConfigurations {
@@ -47,7 +39,7 @@ impl Request for GetAccountConfigurations {
     type Data = ();
     type Response = AccountConfigurations;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "account/configurations".into()
     }
 }
@@ -59,7 +51,7 @@ impl Request for PatchAccountConfigurations {
     type Response = AccountConfigurations;
     const METHOD: Method = Method::PATCH;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "account/configurations".into()
     }
 
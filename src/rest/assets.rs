@@ -3,17 +3,13 @@ use std::borrow::Cow;
 use uuid::Uuid;
 use vila::{Request, RequestData};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum AssetClass {
+    #[default]
     UsEquity,
 }
-impl Default for AssetClass {
-    fn default() -> Self {
-        AssetClass::UsEquity
-    }
-}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "UPPERCASE")]
@@ -27,17 +23,13 @@ pub enum Exchange {
     Otc,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum Status {
+    #[default]
     Active,
     Inactive,
 }
-impl Default for Status {
-    fn default() -> Self {
-        Status::Active
-    }
-}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Asset {
@@ -67,7 +59,7 @@ impl Request for GetAssets {
     type Data = Self;
     type Response = Asset;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "assets".into()
     }
 
@@ -83,7 +75,7 @@ impl Request for GetAsset<'_> {
     type Data = ();
     type Response = Asset;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         format!("assets/{}", self.0).into()
     }
 }
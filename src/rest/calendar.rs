@@ -26,8 +26,8 @@ impl GetCalendar {
 impl Default for GetCalendar {
     fn default() -> Self {
         Self {
-            start: NaiveDate::from_ymd(1970, 1, 1),
-            end: NaiveDate::from_ymd(2029, 12, 31),
+            start: NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2029, 12, 31).unwrap(),
         }
     }
 }
@@ -36,7 +36,7 @@ impl Request for GetCalendar {
     type Data = Self;
     type Response = Vec<Calendar>;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "calendar".into()
     }
 
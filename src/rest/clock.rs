@@ -17,7 +17,7 @@ impl Request for GetClock {
     type Data = ();
     type Response = Clock;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "clock".into()
     }
 }
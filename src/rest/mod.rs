@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use vila::pagination::query::QueryModifier;
 use vila::Client;
 
 pub mod account;
@@ -7,7 +9,9 @@ pub mod assets;
 pub mod calendar;
 pub mod clock;
 pub mod orders;
+pub mod portfolio_history;
 pub mod positions;
+pub mod watchlists;
 
 pub fn paper_client(key: &str, secret: &str) -> Client {
     Client::new("https://paper-api.alpaca.markets").header_auth(vec![
@@ -29,3 +33,15 @@ pub fn client_with_url(url: &str, key: &str, secret: &str) -> Client {
         ("apca-api-secret-key", secret),
     ])
 }
+
+/// An opaque `page_token` used by Alpaca's token-paginated endpoints.
+#[derive(Clone, Debug)]
+pub struct PageToken(pub String);
+
+impl From<PageToken> for QueryModifier {
+    fn from(token: PageToken) -> QueryModifier {
+        let mut data = HashMap::new();
+        data.insert("page_token".into(), token.0);
+        QueryModifier { data }
+    }
+}
@@ -1,24 +1,19 @@
-use crate::common::{Order, OrderClass, OrderType, Side, TimeInForce};
-use chrono::{DateTime, Utc};
+use crate::common::{Order, OrderClass, OrderResponse, OrderType, Quantity, Side, TimeInForce};
+use crate::errors::Error;
+use chrono::{DateTime, Duration, Utc};
+use futures::{stream, Stream, TryStreamExt};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::borrow::Cow;
+use std::collections::HashSet;
 use uuid::Uuid;
-use vila::{Method, Request, RequestData};
-
-impl Default for OrderType {
-    fn default() -> Self {
-        OrderType::Market
-    }
-}
+use vila::{Client, Method, Request, RequestData, StatusCode};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct OrderIntent {
     pub symbol: String,
-    #[serde(
-        deserialize_with = "crate::utils::from_str",
-        serialize_with = "crate::utils::to_string"
-    )]
-    pub qty: usize,
+    #[serde(flatten)]
+    pub quantity: Quantity,
     pub side: Side,
     #[serde(flatten, rename(serialize = "type"))]
     pub order_type: OrderType,
@@ -32,7 +27,7 @@ impl OrderIntent {
     pub fn new(symbol: &str) -> Self {
         OrderIntent {
             symbol: symbol.to_string(),
-            qty: 1,
+            quantity: Quantity::default(),
             side: Side::Buy,
             order_type: OrderType::Market,
             time_in_force: TimeInForce::GoodTilCancelled,
@@ -42,8 +37,15 @@ impl OrderIntent {
         }
     }
 
-    pub fn qty(mut self, qty: usize) -> Self {
-        self.qty = qty;
+    /// Sets the number of shares to trade, replacing any notional amount.
+    pub fn qty<T: Into<Decimal>>(mut self, qty: T) -> Self {
+        self.quantity = Quantity::Qty(qty.into());
+        self
+    }
+
+    /// Sets the dollar amount to trade, replacing any share quantity.
+    pub fn notional<T: Into<Decimal>>(mut self, notional: T) -> Self {
+        self.quantity = Quantity::Notional(notional.into());
         self
     }
 
@@ -78,31 +80,23 @@ impl OrderIntent {
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum QueryOrderStatus {
+    #[default]
     Open,
     Closed,
     All,
 }
-impl Default for QueryOrderStatus {
-    fn default() -> Self {
-        QueryOrderStatus::Open
-    }
-}
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, Default)]
 pub enum Sort {
     #[serde(rename = "asc")]
     Ascending,
+    #[default]
     #[serde(rename = "desc")]
     Descending,
 }
-impl Default for Sort {
-    fn default() -> Self {
-        Sort::Descending
-    }
-}
 
 #[derive(Serialize, Clone, Debug)]
 pub struct GetOrders {
@@ -137,7 +131,7 @@ impl Request for GetOrders {
     type Data = Self;
     type Response = Vec<Order>;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "orders".into()
     }
 
@@ -146,6 +140,93 @@ impl Request for GetOrders {
     }
 }
 
+fn order_timestamp(order: &Order) -> DateTime<Utc> {
+    order.submitted_at.unwrap_or(order.created_at)
+}
+
+struct OrdersPage {
+    request: GetOrders,
+    seen: HashSet<Uuid>,
+}
+
+/// The largest `limit` the orders endpoint accepts.
+const MAX_ORDERS_LIMIT: u16 = 500;
+
+/// Streams every order matching `request`, walking the `after`/`until` window one page of
+/// `limit` orders at a time in the requested `direction`.
+///
+/// Each window reaches one microsecond past the previous page's boundary timestamp so that
+/// orders sharing that timestamp are not skipped; orders returned twice are de-duplicated by `id`. If a
+/// whole page shares the boundary timestamp, `limit` is raised to the maximum of 500 to get past
+/// it, and an error is returned if even that is not enough.
+pub fn stream_orders(
+    client: &Client,
+    request: GetOrders,
+) -> impl Stream<Item = crate::Result<Order>> + Unpin + '_ {
+    let initial = OrdersPage {
+        request,
+        seen: HashSet::new(),
+    };
+    let pages = stream::try_unfold(Some(initial), move |state| async move {
+        let mut state = match state {
+            Some(state) => state,
+            None => return Ok::<_, Error>(None),
+        };
+        // Alpaca timestamps have microsecond precision, so a finer step could be truncated away
+        let step = Duration::microseconds(1);
+        loop {
+            let page = client.send(&state.request).await?;
+            let boundary = match page.last() {
+                Some(order) => order_timestamp(order),
+                None => return Ok(Some((Vec::new(), None))),
+            };
+            let is_last_page = page.len() < state.request.limit as usize;
+            // Orders within one step of the boundary show up again on the next page
+            let at_boundary: HashSet<Uuid> = page
+                .iter()
+                .filter(|order| {
+                    order_timestamp(order) - boundary < step
+                        && boundary - order_timestamp(order) < step
+                })
+                .map(|order| order.id)
+                .collect();
+            let orders: Vec<Order> = page
+                .into_iter()
+                .filter(|order| !state.seen.contains(&order.id))
+                .collect();
+            if is_last_page {
+                return Ok(Some((orders, None)));
+            }
+            if orders.is_empty() {
+                // The whole page shares the boundary timestamp, and more orders may share it
+                if state.request.limit >= MAX_ORDERS_LIMIT {
+                    return Err(vila::Error::Pagination {
+                        msg: format!(
+                            "More than {} orders share the timestamp {}",
+                            MAX_ORDERS_LIMIT, boundary
+                        ),
+                    }
+                    .into());
+                }
+                state.request.limit = MAX_ORDERS_LIMIT;
+                continue;
+            }
+
+            match state.request.direction {
+                Sort::Descending => state.request.until = Some(boundary + step),
+                Sort::Ascending => state.request.after = Some(boundary - step),
+            }
+            state.seen = at_boundary;
+            return Ok(Some((orders, Some(state))));
+        }
+    });
+    Box::pin(
+        pages
+            .map_ok(|orders| stream::iter(orders.into_iter().map(Ok)))
+            .try_flatten(),
+    )
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub struct GetOrder<'a> {
     #[serde(skip)]
@@ -164,7 +245,7 @@ impl Request for GetOrder<'_> {
     type Data = Self;
     type Response = Order;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         format!("orders/{}", self.order_id).into()
     }
 
@@ -173,6 +254,44 @@ impl Request for GetOrder<'_> {
     }
 }
 
+#[derive(Serialize, Clone, Debug)]
+pub struct GetOrderByClientOrderId<'a> {
+    pub client_order_id: &'a str,
+}
+impl<'a> GetOrderByClientOrderId<'a> {
+    pub fn new(client_order_id: &'a str) -> Self {
+        Self { client_order_id }
+    }
+}
+impl Request for GetOrderByClientOrderId<'_> {
+    type Data = Self;
+    type Response = Order;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "orders:by_client_order_id".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+/// Looks up an order by its `client_order_id`, returning `None` instead of an error if Alpaca
+/// has no such order.
+pub async fn find_order_by_client_order_id(
+    client: &Client,
+    client_order_id: &str,
+) -> crate::Result<Option<Order>> {
+    match client
+        .send(&GetOrderByClientOrderId::new(client_order_id))
+        .await
+    {
+        Ok(order) => Ok(Some(order)),
+        Err(vila::Error::ClientError(StatusCode::NOT_FOUND, _)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SubmitOrder(pub OrderIntent);
 impl Request for SubmitOrder {
@@ -180,7 +299,7 @@ impl Request for SubmitOrder {
     type Response = Order;
     const METHOD: Method = Method::POST;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "orders".into()
     }
 
@@ -189,18 +308,72 @@ impl Request for SubmitOrder {
     }
 }
 
+/// The fields of an open order that can be changed in place. Fields left as `None` keep their
+/// current value.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct ReplaceIntent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trail: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
+}
+
+impl ReplaceIntent {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn qty<T: Into<Decimal>>(mut self, qty: T) -> Self {
+        self.qty = Some(qty.into());
+        self
+    }
+
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    pub fn limit_price(mut self, limit_price: Decimal) -> Self {
+        self.limit_price = Some(limit_price);
+        self
+    }
+
+    pub fn stop_price(mut self, stop_price: Decimal) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn trail(mut self, trail: Decimal) -> Self {
+        self.trail = Some(trail);
+        self
+    }
+
+    pub fn client_order_id(mut self, client_order_id: String) -> Self {
+        self.client_order_id = Some(client_order_id);
+        self
+    }
+}
+
 #[derive(Clone, Debug)]
-pub struct ReplaceOrder<'a>(pub &'a str, pub OrderIntent);
+pub struct ReplaceOrder<'a>(pub &'a str, pub ReplaceIntent);
 impl Request for ReplaceOrder<'_> {
-    type Data = OrderIntent;
+    type Data = ReplaceIntent;
     type Response = Order;
-    const METHOD: Method = Method::POST;
+    const METHOD: Method = Method::PATCH;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         format!("orders/{}", self.0).into()
     }
 
-    fn data(&self) -> RequestData<&OrderIntent> {
+    fn data(&self) -> RequestData<&ReplaceIntent> {
         RequestData::Json(&self.1)
     }
 }
@@ -223,26 +396,79 @@ impl Request for CancelOrder<'_> {
     type Response = EmptyResponse;
     const METHOD: Method = Method::DELETE;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         format!("orders/{}", self.0).into()
     }
 }
 
+/// The outcome of cancelling a single order as part of `CancelAllOrders`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "RawCancellationAttempt")]
 pub struct CancellationAttempt {
     pub id: Uuid,
-    pub status: usize,
-    pub body: Order,
+    pub status: u16,
+    pub body: Option<OrderResponse>,
+}
+
+#[derive(Deserialize)]
+struct RawCancellationAttempt {
+    id: Uuid,
+    status: u16,
+    #[serde(default)]
+    body: Option<OrderResponse>,
+}
+
+impl From<RawCancellationAttempt> for CancellationAttempt {
+    fn from(raw: RawCancellationAttempt) -> Self {
+        let RawCancellationAttempt { id, status, body } = raw;
+        Self {
+            id,
+            status,
+            body: body.map(|body| body.with_status(status)),
+        }
+    }
+}
+
+impl CancellationAttempt {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// The attempts of a `CancelAllOrders` request, split by whether the cancellation succeeded.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationSummary {
+    pub canceled: Vec<CancellationAttempt>,
+    pub failed: Vec<CancellationAttempt>,
+}
+
+impl CancellationSummary {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    pub fn failed_ids(&self) -> Vec<Uuid> {
+        self.failed.iter().map(|attempt| attempt.id).collect()
+    }
+}
+
+impl From<Vec<CancellationAttempt>> for CancellationSummary {
+    fn from(attempts: Vec<CancellationAttempt>) -> Self {
+        let (canceled, failed) = attempts
+            .into_iter()
+            .partition(CancellationAttempt::is_success);
+        Self { canceled, failed }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct CancelAllOrders();
 impl Request for CancelAllOrders {
     type Data = ();
-    type Response = Vec<Order>;
+    type Response = Vec<CancellationAttempt>;
     const METHOD: Method = Method::DELETE;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "orders".into()
     }
 }
@@ -251,6 +477,8 @@ impl Request for CancelAllOrders {
 mod tests {
     use super::*;
     use crate::client_with_url;
+    use crate::common::test::order_json;
+    use crate::errors::{ApiErrorCode, ApiErrorKind};
     use mockito::{mock, Matcher};
 
     #[test]
@@ -300,6 +528,72 @@ mod tests {
         let _serialized = serde_json::to_string(&deserialized).unwrap();
     }
 
+    #[test]
+    fn serde_quantity() {
+        let fractional = OrderIntent::new("AAPL").qty(Decimal::new(15, 1));
+        let serialized = serde_json::to_value(&fractional).unwrap();
+        assert_eq!(serialized["qty"], "1.5");
+        assert!(serialized.get("notional").is_none());
+
+        let notional = fractional.notional(100);
+        let serialized = serde_json::to_value(&notional).unwrap();
+        assert_eq!(serialized["notional"], "100");
+        assert!(serialized.get("qty").is_none());
+        let deserialized: OrderIntent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(
+            deserialized.quantity,
+            Quantity::Notional(Decimal::new(100, 0))
+        );
+
+        let json = r#"{
+            "symbol":"AAPL",
+            "qty":"1",
+            "notional":"100",
+            "side":"buy",
+            "type":"market",
+            "time_in_force":"day",
+            "extended_hours":false,
+            "client_order_id":null,
+            "order_class":"simple"
+        }"#;
+        assert!(serde_json::from_str::<OrderIntent>(json).is_err());
+    }
+
+    #[test]
+    fn serde_notional_order() {
+        let json = r#"{
+            "id": "904837e3-3b76-47ec-b432-046db621571b",
+            "client_order_id": "904837e3-3b76-47ec-b432-046db621571b",
+            "created_at": "2018-10-05T05:48:59Z",
+            "updated_at": null,
+            "submitted_at": null,
+            "filled_at": null,
+            "expired_at": null,
+            "canceled_at": null,
+            "failed_at": null,
+            "replaced_at": null,
+            "replaced_by": null,
+            "replaces": null,
+            "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+            "symbol": "AAPL",
+            "asset_class": "us_equity",
+            "notional": "500",
+            "qty": null,
+            "filled_qty": "3.2574",
+            "type": "market",
+            "side": "buy",
+            "time_in_force": "day",
+            "filled_avg_price": "153.5",
+            "status": "partially_filled",
+            "extended_hours": false,
+            "legs": null,
+            "hwm": null
+        }"#;
+        let order: Order = serde_json::from_str(json).unwrap();
+        assert_eq!(order.quantity, Quantity::Notional(Decimal::new(500, 0)));
+        assert_eq!(order.filled_qty, Decimal::new(32574, 4));
+    }
+
     #[tokio::test]
     async fn test_get_order() {
         let _m = mock("GET", "/orders/904837e3-3b76-47ec-b432-046db621571b")
@@ -400,6 +694,124 @@ mod tests {
         client.send(&GetOrders::new()).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_stream_orders() {
+        let a = order_json(
+            "00000000-0000-0000-0000-00000000000a",
+            "2021-01-01T00:00:03Z",
+        );
+        let b = order_json(
+            "00000000-0000-0000-0000-00000000000b",
+            "2021-01-01T00:00:02Z",
+        );
+        let c = order_json(
+            "00000000-0000-0000-0000-00000000000c",
+            "2021-01-01T00:00:02Z",
+        );
+        let d = order_json(
+            "00000000-0000-0000-0000-00000000000d",
+            "2021-01-01T00:00:01Z",
+        );
+        let _first = mock("GET", "/orders")
+            .match_query(Matcher::Exact(
+                "status=all&limit=2&direction=desc&nested=false".into(),
+            ))
+            .with_body(format!("[{},{}]", a, b))
+            .create();
+        let _second = mock("GET", "/orders")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("until".into(), "2021-01-01T00:00:02.000001Z".into()),
+                Matcher::UrlEncoded("limit".into(), "2".into()),
+            ]))
+            .with_body(format!("[{},{}]", b, c))
+            .create();
+        let _third = mock("GET", "/orders")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("until".into(), "2021-01-01T00:00:02.000001Z".into()),
+                Matcher::UrlEncoded("limit".into(), "500".into()),
+            ]))
+            .with_body(format!("[{},{},{}]", b, c, d))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetOrders {
+            status: QueryOrderStatus::All,
+            limit: 2,
+            ..GetOrders::new()
+        };
+        let ids: Vec<String> = stream_orders(&client, request)
+            .map_ok(|order| order.id.to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                "00000000-0000-0000-0000-00000000000a",
+                "00000000-0000-0000-0000-00000000000b",
+                "00000000-0000-0000-0000-00000000000c",
+                "00000000-0000-0000-0000-00000000000d",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_orders_sharing_timestamp() {
+        let orders: Vec<String> = ["e", "f", "1"]
+            .iter()
+            .map(|id| {
+                order_json(
+                    &format!("00000000-0000-0000-0000-00000000000{}", id),
+                    "2021-01-02T00:00:00Z",
+                )
+            })
+            .collect();
+        let _first = mock("GET", "/orders")
+            .match_query(Matcher::Exact(
+                "status=closed&limit=2&direction=desc&nested=false".into(),
+            ))
+            .with_body(format!("[{},{}]", orders[0], orders[1]))
+            .create();
+        let _second = mock("GET", "/orders")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("status".into(), "closed".into()),
+                Matcher::UrlEncoded("until".into(), "2021-01-02T00:00:00.000001Z".into()),
+                Matcher::UrlEncoded("limit".into(), "2".into()),
+            ]))
+            .with_body(format!("[{},{}]", orders[0], orders[1]))
+            .create();
+        let _third = mock("GET", "/orders")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("status".into(), "closed".into()),
+                Matcher::UrlEncoded("until".into(), "2021-01-02T00:00:00.000001Z".into()),
+                Matcher::UrlEncoded("limit".into(), "500".into()),
+            ]))
+            .with_body(format!("[{}]", orders.join(",")))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetOrders {
+            status: QueryOrderStatus::Closed,
+            limit: 2,
+            ..GetOrders::new()
+        };
+        let ids: Vec<String> = stream_orders(&client, request)
+            .map_ok(|order| order.id.to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                "00000000-0000-0000-0000-00000000000e",
+                "00000000-0000-0000-0000-00000000000f",
+                "00000000-0000-0000-0000-000000000001",
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn missing_order() {
         let _m = mock("GET", "/orders/904837e3-3b76-47ec-b432-046db621571b")
@@ -419,6 +831,81 @@ mod tests {
         assert!(res.is_err())
     }
 
+    #[tokio::test]
+    async fn test_find_order_by_client_order_id() {
+        let _found = mock("GET", "/orders:by_client_order_id")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::UrlEncoded(
+                "client_order_id".into(),
+                "my-order".into(),
+            ))
+            .with_body(order_json(
+                "904837e3-3b76-47ec-b432-046db621571b",
+                "2018-10-05T05:48:59Z",
+            ))
+            .create();
+        let _missing = mock("GET", "/orders:by_client_order_id")
+            .match_query(Matcher::UrlEncoded(
+                "client_order_id".into(),
+                "unknown".into(),
+            ))
+            .with_status(404)
+            .with_body(r#"{"code":40410000,"message":"order not found"}"#)
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let order = find_order_by_client_order_id(&client, "my-order")
+            .await
+            .unwrap();
+        assert!(order.is_some());
+        let order = find_order_by_client_order_id(&client, "unknown")
+            .await
+            .unwrap();
+        assert!(order.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replace_order() {
+        let _m = mock("PATCH", "/orders/904837e3-3b76-47ec-b432-046db621571b")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_body(r#"{"qty":"2.5","limit_price":"101.25","client_order_id":"replacement"}"#)
+            .with_body(order_json(
+                "5a6c2bd2-6a1a-4b8e-9b1d-2f7b3c0f1e44",
+                "2018-10-05T05:49:59Z",
+            ))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let intent = ReplaceIntent::new()
+            .qty(Decimal::new(25, 1))
+            .limit_price(Decimal::new(10125, 2))
+            .client_order_id("replacement".into());
+        client
+            .send(&ReplaceOrder(
+                "904837e3-3b76-47ec-b432-046db621571b",
+                intent,
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn serde_replace_intent() {
+        let intent = ReplaceIntent::new()
+            .time_in_force(TimeInForce::GoodTilCancelled)
+            .stop_price(Decimal::new(99, 0))
+            .trail(Decimal::new(15, 1));
+        assert_eq!(
+            serde_json::to_string(&intent).unwrap(),
+            r#"{"time_in_force":"gtc","stop_price":"99","trail":"1.5"}"#
+        );
+        assert_eq!(serde_json::to_string(&ReplaceIntent::new()).unwrap(), "{}");
+    }
+
     #[tokio::test]
     async fn test_cancel_order() {
         let _m = mock("DELETE", "/orders/904837e3-3b76-47ec-b432-046db621571b")
@@ -468,4 +955,41 @@ mod tests {
             .await
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn test_cancel_all_orders() {
+        let _m = mock("DELETE", "/orders")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .with_status(207)
+            .with_body(format!(
+                r#"[
+                    {{"id": "904837e3-3b76-47ec-b432-046db621571b", "status": 200, "body": {}}},
+                    {{"id": "5a6c2bd2-6a1a-4b8e-9b1d-2f7b3c0f1e44", "status": 500, "body": {{"code": 50010000, "message": "internal server error"}}}}
+                ]"#,
+                order_json(
+                    "904837e3-3b76-47ec-b432-046db621571b",
+                    "2018-10-05T05:48:59Z"
+                )
+            ))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let attempts = client.send(&CancelAllOrders()).await.unwrap();
+        let summary = CancellationSummary::from(attempts);
+        assert_eq!(summary.canceled.len(), 1);
+        assert!(!summary.is_complete());
+        assert_eq!(
+            summary.failed_ids(),
+            vec![Uuid::parse_str("5a6c2bd2-6a1a-4b8e-9b1d-2f7b3c0f1e44").unwrap()]
+        );
+        match &summary.failed[0].body {
+            Some(OrderResponse::Error(e)) => {
+                assert_eq!(e.code, ApiErrorCode::InternalServerError);
+                assert_eq!(e.kind(), ApiErrorKind::Server);
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+    }
 }
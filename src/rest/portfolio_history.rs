@@ -0,0 +1,242 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use vila::{Request, RequestData};
+
+/// The span of time covered by a portfolio history, e.g. `Period::Months(1)` for `1M`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Period {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Period::Days(n) => write!(f, "{}D", n),
+            Period::Weeks(n) => write!(f, "{}W", n),
+            Period::Months(n) => write!(f, "{}M", n),
+            Period::Years(n) => write!(f, "{}A", n),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TimeFrame {
+    #[serde(rename = "1Min")]
+    OneMinute,
+    #[serde(rename = "5Min")]
+    FiveMinutes,
+    #[serde(rename = "15Min")]
+    FifteenMinutes,
+    #[serde(rename = "1H")]
+    OneHour,
+    #[serde(rename = "1D")]
+    OneDay,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct GetPortfolioHistory {
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::to_string_optional"
+    )]
+    pub period: Option<Period>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeframe: Option<TimeFrame>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_end: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extended_hours: Option<bool>,
+}
+
+impl GetPortfolioHistory {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn period(mut self, period: Period) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn timeframe(mut self, timeframe: TimeFrame) -> Self {
+        self.timeframe = Some(timeframe);
+        self
+    }
+
+    pub fn date_end(mut self, date_end: NaiveDate) -> Self {
+        self.date_end = Some(date_end);
+        self
+    }
+
+    pub fn extended_hours(mut self, extended_hours: bool) -> Self {
+        self.extended_hours = Some(extended_hours);
+        self
+    }
+}
+
+impl Request for GetPortfolioHistory {
+    type Data = Self;
+    type Response = PortfolioHistory;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "account/portfolio/history".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
+}
+
+/// A single point on the equity curve. Values are `None` for timestamps before the account had
+/// any activity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortfolioHistoryPoint {
+    pub timestamp: DateTime<Utc>,
+    pub equity: Option<Decimal>,
+    pub profit_loss: Option<Decimal>,
+    pub profit_loss_pct: Option<Decimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "RawPortfolioHistory", into = "RawPortfolioHistory")]
+pub struct PortfolioHistory {
+    pub base_value: Decimal,
+    pub timeframe: TimeFrame,
+    pub points: Vec<PortfolioHistoryPoint>,
+}
+
+/// The wire format of a portfolio history, with each series as its own array.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct RawPortfolioHistory {
+    timestamp: Vec<i64>,
+    equity: Vec<Option<Decimal>>,
+    profit_loss: Vec<Option<Decimal>>,
+    profit_loss_pct: Vec<Option<Decimal>>,
+    base_value: Decimal,
+    timeframe: TimeFrame,
+}
+
+impl TryFrom<RawPortfolioHistory> for PortfolioHistory {
+    type Error = String;
+
+    fn try_from(raw: RawPortfolioHistory) -> Result<Self, Self::Error> {
+        let len = raw.timestamp.len();
+        if raw.equity.len() != len
+            || raw.profit_loss.len() != len
+            || raw.profit_loss_pct.len() != len
+        {
+            return Err("portfolio history series have mismatched lengths".into());
+        }
+        let points = raw
+            .timestamp
+            .into_iter()
+            .zip(raw.equity)
+            .zip(raw.profit_loss)
+            .zip(raw.profit_loss_pct)
+            .map(|(((timestamp, equity), profit_loss), profit_loss_pct)| {
+                let timestamp = Utc
+                    .timestamp_opt(timestamp, 0)
+                    .single()
+                    .ok_or_else(|| format!("invalid timestamp: {}", timestamp))?;
+                Ok(PortfolioHistoryPoint {
+                    timestamp,
+                    equity,
+                    profit_loss,
+                    profit_loss_pct,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(PortfolioHistory {
+            base_value: raw.base_value,
+            timeframe: raw.timeframe,
+            points,
+        })
+    }
+}
+
+impl From<PortfolioHistory> for RawPortfolioHistory {
+    fn from(history: PortfolioHistory) -> Self {
+        let mut raw = RawPortfolioHistory {
+            timestamp: Vec::with_capacity(history.points.len()),
+            equity: Vec::with_capacity(history.points.len()),
+            profit_loss: Vec::with_capacity(history.points.len()),
+            profit_loss_pct: Vec::with_capacity(history.points.len()),
+            base_value: history.base_value,
+            timeframe: history.timeframe,
+        };
+        for point in history.points {
+            raw.timestamp.push(point.timestamp.timestamp());
+            raw.equity.push(point.equity);
+            raw.profit_loss.push(point.profit_loss);
+            raw.profit_loss_pct.push(point.profit_loss_pct);
+        }
+        raw
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn test_get_portfolio_history() {
+        let _m = mock("GET", "/account/portfolio/history")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("period".into(), "1W".into()),
+                Matcher::UrlEncoded("timeframe".into(), "1D".into()),
+                Matcher::UrlEncoded("date_end".into(), "2019-08-02".into()),
+                Matcher::UrlEncoded("extended_hours".into(), "true".into()),
+            ]))
+            .with_body(
+                r#"{
+                    "timestamp": [1564617600, 1564704000],
+                    "equity": [null, 10000.25],
+                    "profit_loss": [null, 15.5],
+                    "profit_loss_pct": [null, 0.00155],
+                    "base_value": 9984.75,
+                    "timeframe": "1D"
+                }"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let request = GetPortfolioHistory::new()
+            .period(Period::Weeks(1))
+            .timeframe(TimeFrame::OneDay)
+            .date_end(NaiveDate::from_ymd_opt(2019, 8, 2).unwrap())
+            .extended_hours(true);
+        let history = client.send(&request).await.unwrap();
+        assert_eq!(history.points.len(), 2);
+        assert_eq!(history.points[0].equity, None);
+        assert_eq!(
+            history.points[1].timestamp,
+            Utc.with_ymd_and_hms(2019, 8, 2, 0, 0, 0).unwrap()
+        );
+        assert_eq!(history.points[1].equity, Some(Decimal::new(1000025, 2)));
+    }
+
+    #[test]
+    fn mismatched_series() {
+        let json = r#"{
+            "timestamp": [1564617600, 1564704000],
+            "equity": [10000.25],
+            "profit_loss": [15.5],
+            "profit_loss_pct": [0.00155],
+            "base_value": 9984.75,
+            "timeframe": "1D"
+        }"#;
+        assert!(serde_json::from_str::<PortfolioHistory>(json).is_err());
+    }
+}
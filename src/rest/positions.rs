@@ -1,22 +1,18 @@
+use crate::common::{Order, OrderResponse};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use uuid::Uuid;
-use vila::{Method, Request};
+use vila::{Method, Request, RequestData};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all(serialize = "lowercase", deserialize = "lowercase"))]
 pub enum Side {
+    #[default]
     Long,
     Short,
 }
 
-impl Default for Side {
-    fn default() -> Side {
-        Side::Long
-    }
-}
-
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Position {
     pub asset_id: Uuid,
@@ -24,11 +20,7 @@ pub struct Position {
     pub exchange: String,
     pub asset_class: String,
     pub avg_entry_price: Decimal,
-    #[serde(
-        deserialize_with = "crate::utils::from_str",
-        serialize_with = "crate::utils::to_string"
-    )]
-    pub qty: i32,
+    pub qty: Decimal,
     pub side: Side,
     pub market_value: Decimal,
     pub cost_basis: Decimal,
@@ -47,7 +39,7 @@ impl Request for GetPositions {
     type Data = ();
     type Response = Vec<Position>;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "positions".into()
     }
 }
@@ -58,39 +50,131 @@ impl Request for GetPosition<'_> {
     type Data = ();
     type Response = Position;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         format!("positions/{}", self.0).into()
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct CloseAllPositions;
+/// The outcome of liquidating a single position as part of `CloseAllPositions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(from = "RawPositionClosure")]
+pub struct PositionClosure {
+    pub symbol: String,
+    pub status: u16,
+    pub body: Option<OrderResponse>,
+}
+
+#[derive(Deserialize)]
+struct RawPositionClosure {
+    symbol: String,
+    status: u16,
+    #[serde(default)]
+    body: Option<OrderResponse>,
+}
+
+impl From<RawPositionClosure> for PositionClosure {
+    fn from(raw: RawPositionClosure) -> Self {
+        let RawPositionClosure {
+            symbol,
+            status,
+            body,
+        } = raw;
+        Self {
+            symbol,
+            status,
+            body: body.map(|body| body.with_status(status)),
+        }
+    }
+}
+
+impl PositionClosure {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CloseAllPositions {
+    pub cancel_orders: bool,
+}
+impl CloseAllPositions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Cancel all open orders before liquidating the positions.
+    pub fn cancel_orders(mut self, cancel_orders: bool) -> Self {
+        self.cancel_orders = cancel_orders;
+        self
+    }
+}
 impl Request for CloseAllPositions {
-    type Data = ();
-    type Response = Vec<Position>;
+    type Data = Self;
+    type Response = Vec<PositionClosure>;
     const METHOD: Method = Method::DELETE;
 
-    fn endpoint(&self) -> Cow<str> {
+    fn endpoint(&self) -> Cow<'_, str> {
         "positions".into()
     }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct ClosePosition<'a>(pub &'a str);
+/// Liquidates a position, either entirely or partially by `qty` or `percentage`. Only one of
+/// `qty` and `percentage` may be set; each builder method clears the other.
+#[derive(Serialize, Clone, Debug)]
+pub struct ClosePosition<'a> {
+    #[serde(skip)]
+    pub symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<Decimal>,
+}
+impl<'a> ClosePosition<'a> {
+    pub fn new(symbol: &'a str) -> Self {
+        Self {
+            symbol,
+            qty: None,
+            percentage: None,
+        }
+    }
+
+    pub fn qty<T: Into<Decimal>>(mut self, qty: T) -> Self {
+        self.qty = Some(qty.into());
+        self.percentage = None;
+        self
+    }
+
+    pub fn percentage<T: Into<Decimal>>(mut self, percentage: T) -> Self {
+        self.percentage = Some(percentage.into());
+        self.qty = None;
+        self
+    }
+}
 impl Request for ClosePosition<'_> {
-    type Data = ();
-    type Response = Position;
+    type Data = Self;
+    type Response = Order;
+    const METHOD: Method = Method::DELETE;
 
-    fn endpoint(&self) -> Cow<str> {
-        format!("positions/{}", self.0).into()
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("positions/{}", self.symbol).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Query(self)
     }
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
     use crate::client_with_url;
-    use mockito::mock;
+    use crate::common::test::ORDER;
+    use crate::errors::{ApiErrorCode, ApiErrorKind};
+    use mockito::{mock, Matcher};
 
     #[tokio::test]
     async fn test_get_positions() {
@@ -118,7 +202,58 @@ mod test {
         client.send(&GetPosition("AAPL")).await.unwrap();
     }
 
-    const POSITION: &'static str = r#"{
+    #[tokio::test]
+    async fn test_close_position() {
+        let _m = mock("DELETE", "/positions/AAPL")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::Exact("percentage=50".into()))
+            .with_body(ORDER.replace(r#""side": "buy""#, r#""side": "sell""#))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let order = client
+            .send(&ClosePosition::new("AAPL").qty(1).percentage(50))
+            .await
+            .unwrap();
+        assert_eq!(order.side, crate::common::Side::Sell);
+    }
+
+    #[tokio::test]
+    async fn test_close_all_positions() {
+        let _m = mock("DELETE", "/positions")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::UrlEncoded("cancel_orders".into(), "true".into()))
+            .with_status(207)
+            .with_body(format!(
+                r#"[
+                    {{"symbol": "AAPL", "status": 200, "body": {}}},
+                    {{"symbol": "TSLA", "status": 403, "body": {{"code": 40310000, "message": "insufficient qty available for order"}}}}
+                ]"#,
+                ORDER
+            ))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let closures = client
+            .send(&CloseAllPositions::new().cancel_orders(true))
+            .await
+            .unwrap();
+        assert!(closures[0].is_success());
+        assert!(!closures[1].is_success());
+        match &closures[1].body {
+            Some(OrderResponse::Error(e)) => {
+                assert_eq!(e.code, ApiErrorCode::Forbidden);
+                assert_eq!(e.kind(), ApiErrorKind::Forbidden);
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+    }
+
+    const POSITION: &str = r#"{
 	  "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
 	  "symbol": "AAPL",
 	  "exchange": "NASDAQ",
@@ -137,7 +272,7 @@ mod test {
 	  "change_today": "0.0084"
 	}"#;
 
-    const POSITIONS: &'static str = r#"[{
+    pub(crate) const POSITIONS: &str = r#"[{
 	  "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
 	  "symbol": "AAPL",
 	  "exchange": "NASDAQ",
@@ -0,0 +1,276 @@
+use crate::rest::assets::Asset;
+use crate::rest::orders::EmptyResponse;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use uuid::Uuid;
+use vila::{Method, Request, RequestData};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Watchlist {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Not included when listing all watchlists.
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GetWatchlists;
+impl Request for GetWatchlists {
+    type Data = ();
+    type Response = Vec<Watchlist>;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "watchlists".into()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GetWatchlist<'a>(pub &'a str);
+impl Request for GetWatchlist<'_> {
+    type Data = ();
+    type Response = Watchlist;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("watchlists/{}", self.0).into()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CreateWatchlist {
+    pub name: String,
+    pub symbols: Vec<String>,
+}
+impl CreateWatchlist {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            symbols: Vec::new(),
+        }
+    }
+
+    pub fn symbols(mut self, symbols: Vec<String>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+}
+impl Request for CreateWatchlist {
+    type Data = Self;
+    type Response = Watchlist;
+    const METHOD: Method = Method::POST;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        "watchlists".into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Json(self)
+    }
+}
+
+/// Renames a watchlist and/or replaces its full list of symbols.
+#[derive(Serialize, Clone, Debug)]
+pub struct UpdateWatchlist<'a> {
+    #[serde(skip)]
+    pub watchlist_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<String>>,
+}
+impl<'a> UpdateWatchlist<'a> {
+    pub fn new(watchlist_id: &'a str) -> Self {
+        Self {
+            watchlist_id,
+            name: None,
+            symbols: None,
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn symbols(mut self, symbols: Vec<String>) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+}
+impl Request for UpdateWatchlist<'_> {
+    type Data = Self;
+    type Response = Watchlist;
+    const METHOD: Method = Method::PUT;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("watchlists/{}", self.watchlist_id).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Json(self)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AddAssetToWatchlist<'a> {
+    #[serde(skip)]
+    pub watchlist_id: &'a str,
+    pub symbol: &'a str,
+}
+impl<'a> AddAssetToWatchlist<'a> {
+    pub fn new(watchlist_id: &'a str, symbol: &'a str) -> Self {
+        Self {
+            watchlist_id,
+            symbol,
+        }
+    }
+}
+impl Request for AddAssetToWatchlist<'_> {
+    type Data = Self;
+    type Response = Watchlist;
+    const METHOD: Method = Method::POST;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("watchlists/{}", self.watchlist_id).into()
+    }
+
+    fn data(&self) -> RequestData<&Self> {
+        RequestData::Json(self)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoveAssetFromWatchlist<'a>(pub &'a str, pub &'a str);
+impl Request for RemoveAssetFromWatchlist<'_> {
+    type Data = ();
+    type Response = Watchlist;
+    const METHOD: Method = Method::DELETE;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("watchlists/{}/{}", self.0, self.1).into()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DeleteWatchlist<'a>(pub &'a str);
+impl Request for DeleteWatchlist<'_> {
+    type Data = ();
+    type Response = EmptyResponse;
+    const METHOD: Method = Method::DELETE;
+
+    fn endpoint(&self) -> Cow<'_, str> {
+        format!("watchlists/{}", self.0).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use mockito::mock;
+
+    #[tokio::test]
+    async fn test_get_watchlists() {
+        let _m = mock("GET", "/watchlists")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .with_body(
+                r#"[{
+                    "id": "3174d6df-7726-44b4-a5bd-7fda5ae6e009",
+                    "account_id": "abc2cf58-8a8a-4cc4-a9e0-5fee2b1bfd3c",
+                    "name": "Monday List",
+                    "created_at": "2019-10-30T07:54:42.981322Z",
+                    "updated_at": "2019-10-30T07:54:42.981322Z"
+                }]"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let watchlists = client.send(&GetWatchlists).await.unwrap();
+        assert!(watchlists[0].assets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_watchlist() {
+        let _m = mock("POST", "/watchlists")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_body(r#"{"name":"Monday List","symbols":["AAPL"]}"#)
+            .with_body(WATCHLIST)
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let watchlist = client
+            .send(&CreateWatchlist::new("Monday List").symbols(vec!["AAPL".into()]))
+            .await
+            .unwrap();
+        assert_eq!(watchlist.assets[0].symbol, "AAPL");
+    }
+
+    #[tokio::test]
+    async fn test_add_asset_to_watchlist() {
+        let _m = mock("POST", "/watchlists/3174d6df-7726-44b4-a5bd-7fda5ae6e009")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_body(r#"{"symbol":"AAPL"}"#)
+            .with_body(WATCHLIST)
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        client
+            .send(&AddAssetToWatchlist::new(
+                "3174d6df-7726-44b4-a5bd-7fda5ae6e009",
+                "AAPL",
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_remove_asset_from_watchlist() {
+        let _m = mock(
+            "DELETE",
+            "/watchlists/3174d6df-7726-44b4-a5bd-7fda5ae6e009/AAPL",
+        )
+        .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+        .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+        .with_body(WATCHLIST)
+        .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        client
+            .send(&RemoveAssetFromWatchlist(
+                "3174d6df-7726-44b4-a5bd-7fda5ae6e009",
+                "AAPL",
+            ))
+            .await
+            .unwrap();
+    }
+
+    const WATCHLIST: &str = r#"{
+        "id": "3174d6df-7726-44b4-a5bd-7fda5ae6e009",
+        "account_id": "abc2cf58-8a8a-4cc4-a9e0-5fee2b1bfd3c",
+        "name": "Monday List",
+        "created_at": "2019-10-30T07:54:42.981322Z",
+        "updated_at": "2019-10-30T07:54:42.981322Z",
+        "assets": [{
+            "id": "904837e3-3b76-47ec-b432-046db621571b",
+            "class": "us_equity",
+            "exchange": "NASDAQ",
+            "symbol": "AAPL",
+            "status": "active",
+            "tradable": true,
+            "marginable": true,
+            "shortable": true,
+            "easy_to_borrow": true
+        }]
+    }"#;
+}
@@ -0,0 +1,409 @@
+use crate::common::{Bar, Quote, Trade};
+use crate::errors::{Error, Result};
+use chrono::{DateTime, Utc};
+use futures::{ready, SinkExt, Stream, StreamExt};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, info};
+
+pub const IEX_URL: &str = "wss://stream.data.alpaca.markets/v2/iex";
+pub const SIP_URL: &str = "wss://stream.data.alpaca.markets/v2/sip";
+
+/// The symbols subscribed to on each channel. `"*"` subscribes to every symbol.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trades: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bars: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub daily_bars: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lulds: Vec<String>,
+}
+
+impl Subscription {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn trades(mut self, symbols: Vec<String>) -> Self {
+        self.trades = symbols;
+        self
+    }
+
+    pub fn quotes(mut self, symbols: Vec<String>) -> Self {
+        self.quotes = symbols;
+        self
+    }
+
+    pub fn bars(mut self, symbols: Vec<String>) -> Self {
+        self.bars = symbols;
+        self
+    }
+
+    pub fn daily_bars(mut self, symbols: Vec<String>) -> Self {
+        self.daily_bars = symbols;
+        self
+    }
+
+    pub fn statuses(mut self, symbols: Vec<String>) -> Self {
+        self.statuses = symbols;
+        self
+    }
+
+    pub fn lulds(mut self, symbols: Vec<String>) -> Self {
+        self.lulds = symbols;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+            && self.quotes.is_empty()
+            && self.bars.is_empty()
+            && self.daily_bars.is_empty()
+            && self.statuses.is_empty()
+            && self.lulds.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum MarketDataAction {
+    Auth { key: String, secret: String },
+    Subscribe(Subscription),
+    Unsubscribe(Subscription),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SymbolTrade {
+    #[serde(rename = "S")]
+    pub symbol: String,
+    #[serde(flatten)]
+    pub trade: Trade,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SymbolQuote {
+    #[serde(rename = "S")]
+    pub symbol: String,
+    #[serde(flatten)]
+    pub quote: Quote,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SymbolBar {
+    #[serde(rename = "S")]
+    pub symbol: String,
+    #[serde(flatten)]
+    pub bar: Bar,
+}
+
+/// A trading status change, e.g. a halt or resumption.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Status {
+    #[serde(rename = "S")]
+    pub symbol: String,
+    #[serde(rename = "t")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "sc")]
+    pub status_code: String,
+    #[serde(rename = "sm")]
+    pub status_message: String,
+    #[serde(rename = "rc")]
+    pub reason_code: String,
+    #[serde(rename = "rm")]
+    pub reason_message: String,
+    #[serde(rename = "z")]
+    pub tape: String,
+}
+
+/// A limit up - limit down price band.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Luld {
+    #[serde(rename = "S")]
+    pub symbol: String,
+    #[serde(rename = "t")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "u")]
+    pub limit_up_price: Decimal,
+    #[serde(rename = "d")]
+    pub limit_down_price: Decimal,
+    #[serde(rename = "i")]
+    pub indicator: String,
+    #[serde(rename = "z")]
+    pub tape: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "T")]
+pub enum MarketDataMessage {
+    #[serde(rename = "success")]
+    Success { msg: String },
+    #[serde(rename = "error")]
+    Error { code: u32, msg: String },
+    #[serde(rename = "subscription")]
+    Subscription(Subscription),
+    #[serde(rename = "t")]
+    Trade(SymbolTrade),
+    #[serde(rename = "q")]
+    Quote(SymbolQuote),
+    #[serde(rename = "b")]
+    Bar(SymbolBar),
+    #[serde(rename = "d")]
+    DailyBar(SymbolBar),
+    #[serde(rename = "s")]
+    Status(Status),
+    #[serde(rename = "l")]
+    Luld(Luld),
+    /// A message type this crate does not know about yet.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A stream of market data messages. The server batches messages into JSON arrays, which are
+/// yielded one message at a time.
+pub struct MarketDataStream {
+    inner: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    buffer: VecDeque<MarketDataMessage>,
+}
+
+impl Stream for MarketDataStream {
+    type Item = Result<MarketDataMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(message) = self.buffer.pop_front() {
+                return Poll::Ready(Some(Ok(message)));
+            }
+            let parsed: serde_json::Result<Vec<MarketDataMessage>> =
+                match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
+                    Some(Ok(Message::Text(txt))) => serde_json::from_str(&txt),
+                    Some(Ok(Message::Binary(bits))) => serde_json::from_slice(&bits),
+                    // Control frames carry no market data
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Poll::Ready(Some(Err(Error::Tungstenite(e)))),
+                    None => return Poll::Ready(None),
+                };
+            match parsed {
+                Ok(batch) => self.buffer.extend(batch),
+                Err(e) => return Poll::Ready(Some(Err(Error::from(e)))),
+            }
+        }
+    }
+}
+
+impl MarketDataStream {
+    async fn send_action(&mut self, action: &MarketDataAction) -> Result<()> {
+        debug!("market data action: {:?}", action);
+        self.inner
+            .send(Message::text(serde_json::to_string(action)?))
+            .await
+            .map_err(Error::from)
+    }
+
+    async fn expect_success(&mut self, expected: &str) -> Result<()> {
+        match self.next().await.ok_or(Error::StreamClosed)?? {
+            MarketDataMessage::Success { msg } if msg == expected => Ok(()),
+            MarketDataMessage::Error { code, msg } => Err(Error::MarketData { code, message: msg }),
+            other => Err(Error::ConnectionFailure(format!(
+                "Unexpected message: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Waits for the server to acknowledge a (un)subscription. Data messages received in the
+    /// meantime are kept and yielded by the stream afterwards.
+    async fn await_subscription(&mut self) -> Result<Subscription> {
+        let mut skipped = Vec::new();
+        let reply = loop {
+            match self.next().await.ok_or(Error::StreamClosed)?? {
+                MarketDataMessage::Subscription(subscription) => break Ok(subscription),
+                MarketDataMessage::Error { code, msg } => {
+                    break Err(Error::MarketData { code, message: msg })
+                }
+                other => skipped.push(other),
+            }
+        };
+        for message in skipped.into_iter().rev() {
+            self.buffer.push_front(message);
+        }
+        reply
+    }
+
+    /// Adds symbols to the current subscription, returning the full subscription afterwards.
+    pub async fn subscribe(&mut self, subscription: Subscription) -> Result<Subscription> {
+        self.send_action(&MarketDataAction::Subscribe(subscription))
+            .await?;
+        self.await_subscription().await
+    }
+
+    /// Removes symbols from the current subscription, returning the full subscription
+    /// afterwards.
+    pub async fn unsubscribe(&mut self, subscription: Subscription) -> Result<Subscription> {
+        self.send_action(&MarketDataAction::Unsubscribe(subscription))
+            .await?;
+        self.await_subscription().await
+    }
+}
+
+pub struct MarketDataConnection {
+    url: String,
+    key_id: String,
+    secret_key: String,
+    subscription: Subscription,
+}
+
+impl MarketDataConnection {
+    pub fn new(
+        url: String,
+        key_id: String,
+        secret_key: String,
+        subscription: Subscription,
+    ) -> Self {
+        Self {
+            url,
+            key_id,
+            secret_key,
+            subscription,
+        }
+    }
+
+    pub async fn connect(self) -> Result<MarketDataStream> {
+        let (client, _) = connect_async(&self.url).await?;
+        let mut ws = MarketDataStream {
+            inner: client,
+            buffer: VecDeque::new(),
+        };
+        ws.expect_success("connected").await?;
+        let auth_message = MarketDataAction::Auth {
+            key: self.key_id,
+            secret: self.secret_key,
+        };
+        ws.send_action(&auth_message).await?;
+        ws.expect_success("authenticated").await?;
+        info!("Authorization successful");
+        if !self.subscription.is_empty() {
+            ws.subscribe(self.subscription).await?;
+        }
+        Ok(ws)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    #[test]
+    fn serde_batch() {
+        let json = r#"[
+            {"T":"t","S":"AAPL","i":52983525029461,"x":"V","p":126.55,"s":1,"t":"2021-02-22T15:51:44.208Z","c":["@","I"],"z":"C"},
+            {"T":"l","S":"AAPL","u":133.88,"d":121.13,"i":"B","t":"2021-02-22T15:51:44.208Z","z":"C"},
+            {"T":"x","S":"AAPL","i":52983525029461,"x":"V","a":"C","t":"2021-02-22T15:51:44.208Z","z":"C"}
+        ]"#;
+        let messages: Vec<MarketDataMessage> = serde_json::from_str(json).unwrap();
+        assert!(matches!(&messages[0], MarketDataMessage::Trade(t) if t.trade.size == 1));
+        assert!(matches!(&messages[1], MarketDataMessage::Luld(l) if l.indicator == "B"));
+        assert_eq!(messages[2], MarketDataMessage::Unknown);
+    }
+
+    #[test]
+    fn serde_subscribe() {
+        let action = MarketDataAction::Subscribe(
+            Subscription::new()
+                .trades(vec!["AAPL".into()])
+                .daily_bars(vec!["*".into()]),
+        );
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"action":"subscribe","trades":["AAPL"],"dailyBars":["*"]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_market_data_connection() {
+        let (con_tx, con_rx) = futures_channel::oneshot::channel();
+        tokio::spawn(async move {
+            let listener = TcpListener::bind("127.0.0.1:12346").await.unwrap();
+            con_tx.send(()).unwrap();
+            let (connection, _) = listener.accept().await.expect("No connections to accept");
+            let mut ws = accept_async(connection)
+                .await
+                .expect("Failed to handshake with connection");
+            ws.send(Message::text(r#"[{"T":"success","msg":"connected"}]"#))
+                .await
+                .unwrap();
+            assert_eq!(
+                ws.next().await.unwrap().unwrap(),
+                Message::text(r#"{"action":"auth","key":"key","secret":"secret"}"#)
+            );
+            ws.send(Message::text(r#"[{"T":"success","msg":"authenticated"}]"#))
+                .await
+                .unwrap();
+            assert_eq!(
+                ws.next().await.unwrap().unwrap(),
+                Message::text(r#"{"action":"subscribe","trades":["AAPL"],"quotes":["AAPL"]}"#)
+            );
+            ws.send(Message::text(
+                r#"[
+                    {"T":"subscription","trades":["AAPL"],"quotes":["AAPL"],"bars":[],"dailyBars":[],"statuses":[],"lulds":[]},
+                    {"T":"t","S":"AAPL","i":96921,"x":"D","p":126.55,"s":100,"t":"2021-02-22T15:51:44.208Z","c":["@"],"z":"C"},
+                    {"T":"q","S":"AAPL","bx":"U","bp":126.54,"bs":1,"ax":"Q","ap":126.56,"as":2,"t":"2021-02-22T15:51:44.209Z","c":["R"],"z":"C"}
+                ]"#,
+            ))
+            .await
+            .unwrap();
+            assert_eq!(
+                ws.next().await.unwrap().unwrap(),
+                Message::text(r#"{"action":"unsubscribe","quotes":["AAPL"]}"#)
+            );
+            ws.send(Message::text(
+                r#"[{"T":"subscription","trades":["AAPL"],"quotes":[],"bars":[],"dailyBars":[],"statuses":[],"lulds":[]}]"#,
+            ))
+            .await
+            .unwrap();
+        });
+
+        con_rx.await.expect("Server not ready");
+        let connection = MarketDataConnection::new(
+            "ws://localhost:12346".into(),
+            "key".into(),
+            "secret".into(),
+            Subscription::new()
+                .trades(vec!["AAPL".into()])
+                .quotes(vec!["AAPL".into()]),
+        );
+        let mut ws = connection.connect().await.unwrap();
+        let trade = ws.next().await.unwrap().unwrap();
+        assert!(
+            matches!(trade, MarketDataMessage::Trade(t) if t.trade.price == Decimal::new(12655, 2))
+        );
+        let quote = ws.next().await.unwrap().unwrap();
+        assert!(
+            matches!(quote, MarketDataMessage::Quote(q) if q.symbol == "AAPL" && q.quote.ask_size == 2)
+        );
+
+        let subscription = ws
+            .unsubscribe(Subscription::new().quotes(vec!["AAPL".into()]))
+            .await
+            .unwrap();
+        assert_eq!(
+            subscription,
+            Subscription::new().trades(vec!["AAPL".into()])
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::errors::{Error, Result};
+use crate::stream::reconnect::{Backoff, ConnectionEvent, ReconnectingWebSocket};
+use crate::stream::{AlpacaMessage, Connection, EventKind};
+use futures::{stream, Stream, StreamExt};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// Selects which messages a [`Subscriber`] receives. Every criterion that is set must match, and
+/// criteria on trade updates never match other messages. The default filter matches everything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    pub symbols: Option<HashSet<String>>,
+    pub events: Option<HashSet<EventKind>>,
+    pub client_order_id_prefix: Option<String>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn symbols(mut self, symbols: Vec<String>) -> Self {
+        self.symbols = Some(symbols.into_iter().collect());
+        self
+    }
+
+    pub fn events(mut self, events: Vec<EventKind>) -> Self {
+        self.events = Some(events.into_iter().collect());
+        self
+    }
+
+    pub fn client_order_id_prefix(mut self, prefix: &str) -> Self {
+        self.client_order_id_prefix = Some(prefix.to_string());
+        self
+    }
+
+    pub fn matches(&self, message: &AlpacaMessage) -> bool {
+        let is_unfiltered = self.symbols.is_none()
+            && self.events.is_none()
+            && self.client_order_id_prefix.is_none();
+        match message {
+            AlpacaMessage::TradeUpdates(update) => {
+                self.symbols
+                    .as_ref()
+                    .map_or(true, |symbols| symbols.contains(&update.order.symbol))
+                    && self
+                        .events
+                        .as_ref()
+                        .map_or(true, |events| events.contains(&update.event.kind()))
+                    && self.client_order_id_prefix.as_ref().map_or(true, |prefix| {
+                        update.order.client_order_id.starts_with(prefix.as_str())
+                    })
+            }
+            _ => is_unfiltered,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Broadcast {
+    Event(Arc<ConnectionEvent>),
+    /// Forwarding stopped, e.g. because reconnecting was given up.
+    Ended,
+}
+
+/// Fans the messages of a single connection out to any number of subscribers. Every subscriber
+/// is also told when the connection drops and when it is re-established.
+///
+/// Messages are buffered per subscriber up to the hub's capacity. A subscriber that falls further
+/// behind skips the oldest messages and is told how many it missed, so a slow consumer never
+/// blocks the socket or the other subscribers.
+pub struct Hub {
+    sender: broadcast::Sender<Broadcast>,
+}
+
+impl Hub {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Registers a subscriber. It only receives messages forwarded after this call.
+    pub fn subscribe(&self, name: &str, filter: Filter) -> Subscriber {
+        Subscriber {
+            name: name.to_string(),
+            filter,
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Connects and forwards every event, reconnecting with `backoff` whenever the connection
+    /// drops, until reconnecting is given up.
+    pub async fn run(&self, connection: Connection, backoff: Backoff) -> Result<()> {
+        match ReconnectingWebSocket::connect(connection, backoff).await {
+            Ok(ws) => self.forward(ws).await,
+            Err(e) => {
+                drop(self.sender.send(Broadcast::Ended));
+                Err(e)
+            }
+        }
+    }
+
+    /// Forwards every event of `events`, such as a [`ReconnectingWebSocket`], until it ends or
+    /// fails, and then tells every subscriber that the stream ended. Messages that fail to parse
+    /// are logged and skipped. A plain [`WebSocket`](crate::stream::WebSocket) can be forwarded
+    /// with `ws.map_ok(ConnectionEvent::Message)`.
+    pub async fn forward<S>(&self, mut events: S) -> Result<()>
+    where
+        S: Stream<Item = Result<ConnectionEvent>> + Unpin,
+    {
+        let result = loop {
+            match events.next().await {
+                // Having no subscribers is fine, the event is simply dropped
+                Some(Ok(event)) => drop(self.sender.send(Broadcast::Event(Arc::new(event)))),
+                Some(Err(Error::Serde(e))) => warn!("Skipping unparseable message: {}", e),
+                Some(Err(e)) => break Err(e),
+                None => break Ok(()),
+            }
+        };
+        drop(self.sender.send(Broadcast::Ended));
+        result
+    }
+}
+
+pub struct Subscriber {
+    name: String,
+    filter: Filter,
+    receiver: broadcast::Receiver<Broadcast>,
+}
+
+impl Subscriber {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Receives the next message matching this subscriber's filter, or the next `Disconnected`
+    /// or `Reconnected` event, which are never filtered out. Returns `Error::StreamClosed`
+    /// whenever the hub stops forwarding a stream, and `None` once the hub has been dropped and
+    /// every buffered event has been received.
+    pub async fn recv(&mut self) -> Option<Result<Arc<ConnectionEvent>>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(Broadcast::Event(event)) => match &*event {
+                    ConnectionEvent::Message(message) if !self.filter.matches(message) => continue,
+                    _ => return Some(Ok(event)),
+                },
+                Ok(Broadcast::Ended) => return Some(Err(Error::StreamClosed)),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Subscriber {} lagged by {} messages", self.name, skipped);
+                    return Some(Err(Error::Lagged {
+                        subscriber: self.name.clone(),
+                        skipped,
+                    }));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<Arc<ConnectionEvent>>> + Unpin {
+        Box::pin(stream::unfold(self, |mut subscriber| async move {
+            subscriber.recv().await.map(|message| (message, subscriber))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::test::ORDER;
+    use crate::common::Order;
+    use crate::stream::{Event, OrderEvent};
+    use futures::future;
+
+    fn trade_update(event: Event, symbol: &str, client_order_id: &str) -> ConnectionEvent {
+        let mut order: Order = serde_json::from_str(ORDER).unwrap();
+        order.symbol = symbol.to_string();
+        order.client_order_id = client_order_id.to_string();
+        ConnectionEvent::Message(AlpacaMessage::TradeUpdates(OrderEvent { event, order }))
+    }
+
+    fn listening() -> ConnectionEvent {
+        ConnectionEvent::Message(AlpacaMessage::Listening { streams: vec![] })
+    }
+
+    #[tokio::test]
+    async fn test_filtered_fan_out() {
+        let hub = Hub::new(16);
+        let mut everything = hub.subscribe("audit", Filter::new());
+        let mut risk = hub.subscribe(
+            "risk",
+            Filter::new()
+                .symbols(vec!["AAPL".into()])
+                .events(vec![EventKind::New]),
+        );
+        let strategy = hub.subscribe("strategy", Filter::new().client_order_id_prefix("mm-"));
+        assert_eq!(hub.subscriber_count(), 3);
+
+        let events = vec![
+            Ok(listening()),
+            Ok(trade_update(Event::New, "AAPL", "mm-1")),
+            Ok(trade_update(Event::New, "MSFT", "mm-2")),
+            Ok(trade_update(Event::PendingNew, "AAPL", "other-1")),
+        ];
+        hub.forward(stream::iter(events)).await.unwrap();
+        drop(hub);
+
+        let mut count = 0;
+        while let Some(Ok(_)) = everything.recv().await {
+            count += 1;
+        }
+        assert_eq!(count, 4);
+        assert!(everything.recv().await.is_none());
+
+        let first = risk.recv().await.unwrap().unwrap();
+        assert_eq!(*first, trade_update(Event::New, "AAPL", "mm-1"));
+        assert!(matches!(risk.recv().await, Some(Err(Error::StreamClosed))));
+        assert!(risk.recv().await.is_none());
+
+        let ids: Vec<String> = strategy
+            .into_stream()
+            .take_while(|event| future::ready(event.is_ok()))
+            .map(|event| match &*event.unwrap() {
+                ConnectionEvent::Message(AlpacaMessage::TradeUpdates(update)) => {
+                    update.order.client_order_id.clone()
+                }
+                other => panic!("Unexpected event {:?}", other),
+            })
+            .collect()
+            .await;
+        assert_eq!(ids, vec!["mm-1", "mm-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber() {
+        let hub = Hub::new(2);
+        let mut slow = hub.subscribe("slow", Filter::new());
+        let events = std::iter::repeat(listening()).take(5).map(Ok);
+        hub.forward(stream::iter(events)).await.unwrap();
+
+        match slow.recv().await.unwrap() {
+            Err(Error::Lagged {
+                subscriber,
+                skipped,
+            }) => {
+                assert_eq!(subscriber, "slow");
+                assert_eq!(skipped, 4);
+            }
+            other => panic!("Expected lag, got {:?}", other),
+        }
+        // Afterwards the subscriber continues with the oldest retained message
+        assert!(slow.recv().await.unwrap().is_ok());
+        assert!(matches!(slow.recv().await, Some(Err(Error::StreamClosed))));
+    }
+
+    #[tokio::test]
+    async fn test_reconnection() {
+        let hub = Hub::new(16);
+        let mut risk = hub.subscribe("risk", Filter::new().symbols(vec!["AAPL".into()]));
+        let events = vec![
+            Ok(trade_update(Event::New, "AAPL", "mm-1")),
+            Ok(ConnectionEvent::Disconnected),
+            Ok(ConnectionEvent::Reconnected { attempts: 2 }),
+            Ok(trade_update(Event::New, "MSFT", "mm-2")),
+            Ok(trade_update(Event::New, "AAPL", "mm-3")),
+        ];
+        hub.forward(stream::iter(events)).await.unwrap();
+
+        // Connection events reach every subscriber, whatever its filter
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            received.push((*risk.recv().await.unwrap().unwrap()).clone());
+        }
+        assert_eq!(
+            received,
+            vec![
+                trade_update(Event::New, "AAPL", "mm-1"),
+                ConnectionEvent::Disconnected,
+                ConnectionEvent::Reconnected { attempts: 2 },
+                trade_update(Event::New, "AAPL", "mm-3"),
+            ]
+        );
+        assert!(matches!(risk.recv().await, Some(Err(Error::StreamClosed))));
+    }
+
+    #[tokio::test]
+    async fn test_stream_ended() {
+        let hub = Hub::new(16);
+        let mut subscriber = hub.subscribe("audit", Filter::new());
+        let events = vec![
+            Ok(listening()),
+            Err(Error::StaleConnection(std::time::Duration::from_secs(1))),
+        ];
+        assert!(hub.forward(stream::iter(events)).await.is_err());
+
+        // The hub is still alive, but subscribers learn that the feed is gone
+        assert!(subscriber.recv().await.unwrap().is_ok());
+        assert!(matches!(
+            subscriber.recv().await,
+            Some(Err(Error::StreamClosed))
+        ));
+
+        // Forwarding a new stream resumes delivery
+        hub.forward(stream::iter(vec![Ok(listening())]))
+            .await
+            .unwrap();
+        assert!(subscriber.recv().await.unwrap().is_ok());
+    }
+}
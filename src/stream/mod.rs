@@ -1,45 +1,165 @@
 use crate::errors::{Error, Result};
-use futures::{ready, Sink, SinkExt, Stream, StreamExt};
+use crate::stream::record::Recorder;
+use futures::stream::{SplitSink, SplitStream};
+use futures::{ready, Future, Sink, SinkExt, Stream, StreamExt};
+use std::collections::{HashSet, VecDeque};
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::net::TcpStream;
+use tokio::time::{interval_at, sleep, Instant, Interval, MissedTickBehavior, Sleep};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 use tracing::{debug, info};
 
+pub mod data;
+pub mod hub;
+pub mod reconnect;
+pub mod record;
 pub mod types;
 pub use types::*;
 
 pub struct WebSocket {
     inner: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    keepalive: Option<Interval>,
+    ping_pending: bool,
+    flush_pending: bool,
+    stale_timeout: Option<Duration>,
+    deadline: Option<Pin<Box<Sleep>>>,
+    closed: bool,
+    streams: Vec<StreamName>,
+    pending: VecDeque<AlpacaMessage>,
+    recorder: Option<Recorder>,
+}
+
+impl WebSocket {
+    fn new(
+        inner: WebSocketStream<MaybeTlsStream<TcpStream>>,
+        ping_interval: Option<Duration>,
+        stale_timeout: Option<Duration>,
+    ) -> Self {
+        let keepalive = ping_interval.map(|period| {
+            let mut interval = interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let deadline = stale_timeout.map(|timeout| Box::pin(sleep(timeout)));
+        Self {
+            inner,
+            keepalive,
+            ping_pending: false,
+            flush_pending: false,
+            stale_timeout,
+            deadline,
+            closed: false,
+            streams: Vec::new(),
+            pending: VecDeque::new(),
+            recorder: None,
+        }
+    }
+
+    /// Sends a ping whenever the keepalive interval elapses. Pongs, like any other frame, reset
+    /// the stale-connection deadline.
+    #[allow(clippy::result_large_err)]
+    fn poll_keepalive(&mut self, cx: &mut Context) -> Result<()> {
+        if let Some(keepalive) = &mut self.keepalive {
+            while keepalive.poll_tick(cx).is_ready() {
+                self.ping_pending = true;
+            }
+        }
+        if self.ping_pending {
+            if let Poll::Ready(ready) = Pin::new(&mut self.inner).poll_ready(cx) {
+                ready?;
+                Pin::new(&mut self.inner).start_send(Message::Ping(Vec::new()))?;
+                self.ping_pending = false;
+                self.flush_pending = true;
+            }
+        }
+        if self.flush_pending {
+            if let Poll::Ready(flushed) = Pin::new(&mut self.inner).poll_flush(cx) {
+                flushed?;
+                self.flush_pending = false;
+            }
+        }
+        Ok(())
+    }
+
+    fn reset_deadline(&mut self) {
+        if let (Some(deadline), Some(timeout)) = (&mut self.deadline, self.stale_timeout) {
+            deadline.as_mut().reset(Instant::now() + timeout);
+        }
+    }
 }
 
 impl Stream for WebSocket {
     type Item = Result<AlpacaMessage>;
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
-            Some(Ok(item)) => {
-                match item {
-                    Message::Binary(bits) => {
-                        let parsed: Result<AlpacaMessage> =
-                            serde_json::from_slice(&bits).map_err(Error::from);
-                        Poll::Ready(Some(parsed))
-                    }
-                    Message::Text(txt) => {
-                        let parsed: Result<AlpacaMessage> =
-                            serde_json::from_str(&txt).map_err(Error::from);
-                        Poll::Ready(Some(parsed))
-                    }
-                    _ => {
-                        // Non Text message received, immediately schedule re-poll
-                        cx.waker().wake_by_ref();
-                        Poll::Pending
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(message) = this.pending.pop_front() {
+            return Poll::Ready(Some(Ok(message)));
+        }
+        if this.closed {
+            return Poll::Ready(None);
+        }
+        if let Err(e) = this.poll_keepalive(cx) {
+            return Poll::Ready(Some(Err(e)));
+        }
+        if let (Some(deadline), Some(timeout)) = (&mut this.deadline, this.stale_timeout) {
+            if deadline.as_mut().poll(cx).is_ready() {
+                this.closed = true;
+                return Poll::Ready(Some(Err(Error::StaleConnection(timeout))));
+            }
+        }
+        loop {
+            match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+                Some(Ok(item)) => {
+                    this.reset_deadline();
+                    match item {
+                        Message::Binary(bits) => {
+                            if let Some(recorder) = &this.recorder {
+                                if let Err(e) = recorder.record(&String::from_utf8_lossy(&bits)) {
+                                    return Poll::Ready(Some(Err(e.into())));
+                                }
+                            }
+                            let parsed: Result<AlpacaMessage> =
+                                serde_json::from_slice(&bits).map_err(Error::from);
+                            return Poll::Ready(Some(parsed));
+                        }
+                        Message::Text(txt) => {
+                            if let Some(recorder) = &this.recorder {
+                                if let Err(e) = recorder.record(&txt) {
+                                    return Poll::Ready(Some(Err(e.into())));
+                                }
+                            }
+                            let parsed: Result<AlpacaMessage> =
+                                serde_json::from_str(&txt).map_err(Error::from);
+                            return Poll::Ready(Some(parsed));
+                        }
+                        Message::Close(frame) => {
+                            this.closed = true;
+                            debug!("Close frame received: {:?}", frame);
+                            return match frame {
+                                Some(frame) if frame.code != CloseCode::Normal => {
+                                    Poll::Ready(Some(Err(Error::ConnectionClosed {
+                                        code: frame.code.into(),
+                                        reason: frame.reason.into_owned(),
+                                    })))
+                                }
+                                _ => Poll::Ready(None),
+                            };
+                        }
+                        // Pings are answered by tungstenite itself
+                        Message::Ping(_) | Message::Pong(_) => continue,
                     }
                 }
+                Some(Err(e)) => return Poll::Ready(Some(Err(Error::Tungstenite(e)))),
+                None => {
+                    this.closed = true;
+                    return Poll::Ready(None);
+                }
             }
-            Some(Err(e)) => Poll::Ready(Some(Err(Error::Tungstenite(e)))),
-            None => Poll::Ready(None),
         }
     }
 }
@@ -73,38 +193,177 @@ impl<T: Into<String>> Sink<T> for WebSocket {
 }
 
 impl WebSocket {
-    pub async fn subscribe(&mut self, events: Vec<String>) -> Result<()> {
-        let subscription_message = AlpacaAction::Listen { streams: events };
+    /// Records every frame received from now on.
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Stops recording, waiting until every frame received so far has been written.
+    pub async fn stop_recording(&mut self) -> Result<()> {
+        match self.recorder.take() {
+            Some(recorder) => Ok(recorder.finish().await?),
+            None => Ok(()),
+        }
+    }
+
+    /// The streams the server last confirmed it is listening to.
+    pub fn streams(&self) -> &[StreamName] {
+        &self.streams
+    }
+
+    /// Replaces the streams listened to, failing if the server's `Listening` reply does not
+    /// match the request.
+    pub async fn subscribe(&mut self, streams: Vec<StreamName>) -> Result<()> {
+        let subscription_message = AlpacaAction::Listen {
+            streams: streams.clone(),
+        };
 
         debug!("subscription message: {:?}", &subscription_message);
         self.send(&serde_json::to_string(&subscription_message)?)
             .await?;
-        let parsed = self.next().await.ok_or(Error::StreamClosed)?;
-        debug!("Subscription reply: {:?}", &parsed);
+        // Updates may still arrive before the reply; keep them for the stream
+        let mut skipped = Vec::new();
+        let actual = loop {
+            match self.next().await.ok_or(Error::StreamClosed)?? {
+                AlpacaMessage::Listening { streams } => break streams,
+                other => skipped.push(other),
+            }
+        };
+        debug!("Subscription reply: {:?}", &actual);
+        for message in skipped.into_iter().rev() {
+            self.pending.push_front(message);
+        }
+        // Track what the server actually listens to, even if it is not what was requested
+        self.streams = actual.clone();
+        let requested: HashSet<_> = streams.iter().collect();
+        if requested != actual.iter().collect() {
+            return Err(Error::SubscriptionMismatch {
+                requested: streams,
+                actual,
+            });
+        }
         Ok(())
     }
+
+    /// Starts listening to `streams` in addition to the current ones.
+    pub async fn add_streams(&mut self, streams: &[StreamName]) -> Result<()> {
+        let mut all = self.streams.clone();
+        for stream in streams {
+            if !all.contains(stream) {
+                all.push(*stream);
+            }
+        }
+        self.subscribe(all).await
+    }
+
+    /// Stops listening to `streams`, keeping the others.
+    pub async fn remove_streams(&mut self, streams: &[StreamName]) -> Result<()> {
+        let remaining = self
+            .streams
+            .iter()
+            .filter(|stream| !streams.contains(stream))
+            .copied()
+            .collect();
+        self.subscribe(remaining).await
+    }
+
+    /// Splits the socket so that one task can read messages while another sends actions.
+    /// Keepalive pings keep being sent while the reader is polled.
+    pub fn split(self) -> (WebSocketReader, WebSocketWriter) {
+        let (sink, stream) = StreamExt::split::<String>(self);
+        (
+            WebSocketReader { inner: stream },
+            WebSocketWriter { inner: sink },
+        )
+    }
+}
+
+/// The reading half of a [`WebSocket`].
+pub struct WebSocketReader {
+    inner: SplitStream<WebSocket>,
+}
+
+impl Stream for WebSocketReader {
+    type Item = Result<AlpacaMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// The writing half of a [`WebSocket`]. Replies to the actions sent, such as `Listening`, are
+/// received by the corresponding [`WebSocketReader`].
+pub struct WebSocketWriter {
+    inner: SplitSink<WebSocket, String>,
+}
+
+impl WebSocketWriter {
+    /// Replaces the streams listened to.
+    pub async fn listen(&mut self, streams: Vec<StreamName>) -> Result<()> {
+        self.send(AlpacaAction::Listen { streams }).await
+    }
+}
+
+impl Sink<AlpacaAction> for WebSocketWriter {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.inner.poll_ready_unpin(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: AlpacaAction) -> Result<()> {
+        debug!("action: {:?}", &item);
+        let serialized = serde_json::to_string(&item)?;
+        self.inner.start_send_unpin(serialized)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.inner.poll_flush_unpin(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.inner.poll_close_unpin(cx)
+    }
 }
 
+#[derive(Clone)]
 pub struct Connection {
     url: String,
     key_id: String,
     secret_key: String,
-    events: Vec<String>,
+    events: Vec<StreamName>,
+    ping_interval: Option<Duration>,
+    stale_timeout: Option<Duration>,
 }
 
 impl Connection {
-    pub fn new(url: String, key_id: String, secret_key: String, events: Vec<String>) -> Self {
+    pub fn new(url: String, key_id: String, secret_key: String, events: Vec<StreamName>) -> Self {
         Self {
             url,
             key_id,
             secret_key,
             events,
+            ping_interval: None,
+            stale_timeout: None,
         }
     }
 
+    /// Sends a ping at this interval to keep the connection alive.
+    pub fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = Some(ping_interval);
+        self
+    }
+
+    /// Fails the stream with `Error::StaleConnection` if no frame arrives within this timeout.
+    pub fn stale_timeout(mut self, stale_timeout: Duration) -> Self {
+        self.stale_timeout = Some(stale_timeout);
+        self
+    }
+
     pub async fn connect(self) -> Result<WebSocket> {
         let (client, _) = connect_async(&self.url).await?;
-        let mut ws = WebSocket { inner: client };
+        let mut ws = WebSocket::new(client, self.ping_interval, self.stale_timeout);
         let auth_message = AlpacaAction::Authenticate {
             key_id: self.key_id.clone(),
             secret_key: self.secret_key.clone(),
@@ -125,13 +384,17 @@ impl Connection {
 }
 
 #[cfg(test)]
-mod test {
-    use super::Connection;
-    use futures::{SinkExt, StreamExt};
+pub(crate) mod test {
+    use super::{AlpacaMessage, Connection, StreamName};
+    use crate::errors::Error;
+    use futures::{Future, SinkExt, StreamExt};
+    use std::borrow::Cow;
+    use std::time::Duration;
     use tokio::{
         io::{AsyncRead, AsyncWrite},
-        net::TcpListener,
+        net::{TcpListener, TcpStream},
     };
+    use tokio_tungstenite::tungstenite::protocol::frame::{coding::CloseCode, CloseFrame};
     use tokio_tungstenite::tungstenite::Message;
     use tokio_tungstenite::{accept_async, WebSocketStream};
 
@@ -199,11 +462,215 @@ mod test {
             "ws://localhost:12345".into(),
             "key".into(),
             "secret".into(),
-            vec!["account_updates".into(), "trade_updates".into()],
+            vec![StreamName::AccountUpdates, StreamName::TradeUpdates],
         );
 
         let _ws = connection.connect().await.unwrap();
         //let account_update_message = ws.next().await.unwrap();
         //let trade_update_message = ws.next().await.unwrap();
     }
+
+    /// Accepts a single connection on `addr`, completes the handshake and then hands the socket
+    /// to `handler`.
+    pub(crate) async fn serve<F, Fut>(addr: &'static str, handler: F)
+    where
+        F: FnOnce(WebSocketStream<TcpStream>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let (con_tx, con_rx) = futures_channel::oneshot::channel();
+        tokio::spawn(async move {
+            let listener = TcpListener::bind(addr).await.unwrap();
+            con_tx.send(()).unwrap();
+            let (connection, _) = listener.accept().await.expect("No connections to accept");
+            let mut stream = accept_async(connection)
+                .await
+                .expect("Failed to handshake with connection");
+            handshake(&mut stream, r#"["trade_updates"]"#).await;
+            handler(stream).await;
+        });
+        con_rx.await.expect("Server not ready");
+    }
+
+    /// Authenticates the client and confirms its request to listen to `streams`, a JSON array.
+    pub(crate) async fn handshake(connection: &mut WebSocketStream<TcpStream>, streams: &str) {
+        connection.next().await.unwrap().unwrap();
+        connection
+            .send(Message::text(
+                r#"{"stream":"authorization","data":{"status":"authorized","action":"authenticate"}}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            connection.next().await.unwrap().unwrap(),
+            Message::text(format!(
+                r#"{{"action":"listen","data":{{"streams":{}}}}}"#,
+                streams
+            ))
+        );
+        connection
+            .send(Message::text(format!(
+                r#"{{"stream":"listening","data":{{"streams":{}}}}}"#,
+                streams
+            )))
+            .await
+            .unwrap();
+    }
+
+    pub(crate) fn connection(url: &str) -> Connection {
+        Connection::new(
+            url.into(),
+            "key".into(),
+            "secret".into(),
+            vec![StreamName::TradeUpdates],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_close_frame() {
+        serve("127.0.0.1:12348", |mut stream| async move {
+            stream
+                .close(Some(CloseFrame {
+                    code: CloseCode::Policy,
+                    reason: Cow::from("connection limit exceeded"),
+                }))
+                .await
+                .unwrap();
+        })
+        .await;
+
+        let mut ws = connection("ws://localhost:12348").connect().await.unwrap();
+        match ws.next().await.unwrap() {
+            Err(Error::ConnectionClosed { code, reason }) => {
+                assert_eq!(code, 1008);
+                assert_eq!(reason, "connection limit exceeded");
+            }
+            other => panic!("Expected close error, got {:?}", other),
+        }
+        assert!(ws.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_ping() {
+        serve("127.0.0.1:12349", |mut stream| async move {
+            let ping = stream.next().await.unwrap().unwrap();
+            assert!(ping.is_ping());
+            stream.close(None).await.unwrap();
+        })
+        .await;
+
+        let mut ws = connection("ws://localhost:12349")
+            .ping_interval(Duration::from_millis(50))
+            .connect()
+            .await
+            .unwrap();
+        assert!(ws.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stale_connection() {
+        serve("127.0.0.1:12350", |mut stream| async move {
+            // Stay silent until the client gives up
+            stream.next().await;
+        })
+        .await;
+
+        let mut ws = connection("ws://localhost:12350")
+            .stale_timeout(Duration::from_millis(100))
+            .connect()
+            .await
+            .unwrap();
+        assert!(matches!(
+            ws.next().await.unwrap(),
+            Err(Error::StaleConnection(_))
+        ));
+        assert!(ws.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_streams() {
+        serve("127.0.0.1:12351", |mut stream| async move {
+            assert_eq!(
+                stream.next().await.unwrap().unwrap(),
+                Message::text(
+                    r#"{"action":"listen","data":{"streams":["trade_updates","account_updates"]}}"#
+                )
+            );
+            stream
+                .send(Message::text(
+                    r#"{"stream":"account_updates","data":{"id":"ef505a9a-2f3c-4b8a-be95-6b6f185f8a03","created_at":"2018-02-26T19:22:31Z","updated_at":"2018-02-27T18:16:24Z","deleted_at":null,"status":"ACTIVE","currency":"USD","cash":"1241.54","cash_withdrawable":"523.71"}}"#,
+                ))
+                .await
+                .unwrap();
+            stream
+                .send(Message::text(
+                    r#"{"stream":"listening","data":{"streams":["account_updates","trade_updates"]}}"#,
+                ))
+                .await
+                .unwrap();
+            assert_eq!(
+                stream.next().await.unwrap().unwrap(),
+                Message::text(r#"{"action":"listen","data":{"streams":["account_updates"]}}"#)
+            );
+            stream
+                .send(Message::text(
+                    r#"{"stream":"listening","data":{"streams":["trade_updates"]}}"#,
+                ))
+                .await
+                .unwrap();
+        })
+        .await;
+
+        let mut ws = connection("ws://localhost:12351").connect().await.unwrap();
+        assert_eq!(ws.streams(), &[StreamName::TradeUpdates]);
+        ws.add_streams(&[StreamName::AccountUpdates]).await.unwrap();
+        assert_eq!(
+            ws.streams(),
+            &[StreamName::AccountUpdates, StreamName::TradeUpdates]
+        );
+        // The update received while waiting for the reply is not lost
+        assert!(matches!(
+            ws.next().await.unwrap().unwrap(),
+            AlpacaMessage::AccountUpdates { .. }
+        ));
+
+        match ws.remove_streams(&[StreamName::TradeUpdates]).await {
+            Err(Error::SubscriptionMismatch { requested, actual }) => {
+                assert_eq!(requested, vec![StreamName::AccountUpdates]);
+                assert_eq!(actual, vec![StreamName::TradeUpdates]);
+            }
+            other => panic!("Expected subscription mismatch, got {:?}", other),
+        }
+        assert_eq!(ws.streams(), &[StreamName::TradeUpdates]);
+    }
+
+    #[tokio::test]
+    async fn test_split() {
+        serve("127.0.0.1:12352", |mut stream| async move {
+            assert_eq!(
+                stream.next().await.unwrap().unwrap(),
+                Message::text(r#"{"action":"listen","data":{"streams":["account_updates"]}}"#)
+            );
+            stream
+                .send(Message::text(
+                    r#"{"stream":"listening","data":{"streams":["account_updates"]}}"#,
+                ))
+                .await
+                .unwrap();
+        })
+        .await;
+
+        let ws = connection("ws://localhost:12352").connect().await.unwrap();
+        let (mut reader, mut writer) = ws.split();
+        let read = tokio::spawn(async move { reader.next().await.unwrap().unwrap() });
+        writer
+            .listen(vec![StreamName::AccountUpdates])
+            .await
+            .unwrap();
+        assert_eq!(
+            read.await.unwrap(),
+            AlpacaMessage::Listening {
+                streams: vec![StreamName::AccountUpdates]
+            }
+        );
+    }
 }
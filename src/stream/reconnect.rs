@@ -0,0 +1,346 @@
+use crate::errors::{Error, Result};
+use crate::stream::record::Recorder;
+use crate::stream::{AlpacaMessage, Connection, StreamName, WebSocket};
+use futures::future::BoxFuture;
+use futures::{ready, FutureExt, Stream, StreamExt};
+use rand::Rng;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Exponential backoff between reconnection attempts. Each delay is drawn uniformly from the
+/// upper half of the current backoff window to avoid many clients reconnecting in lockstep.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backoff {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Give up after this many consecutive failed attempts. `None` retries forever.
+    pub max_retries: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_retries: None,
+        }
+    }
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// The delay before the given (zero-based) reconnection attempt.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let window = self
+            .initial_delay
+            .checked_mul(2u32.saturating_pow(attempt))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        let half = window / 2;
+        half + rand::thread_rng().gen_range(Duration::ZERO..=window - half)
+    }
+}
+
+/// An event from a [`ReconnectingWebSocket`].
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionEvent {
+    Message(AlpacaMessage),
+    /// The connection dropped. Messages may be missed until `Reconnected` is received, so
+    /// consumers should reconcile their state via the REST API afterwards.
+    Disconnected,
+    /// The connection was re-established, re-authenticated and resubscribed after the given
+    /// number of attempts.
+    Reconnected {
+        attempts: u32,
+    },
+}
+
+#[allow(clippy::large_enum_variant)]
+enum State {
+    Connected(WebSocket),
+    Reconnecting {
+        attempt: u32,
+        future: BoxFuture<'static, Result<WebSocket>>,
+    },
+    Closed,
+}
+
+/// A trade stream that transparently reconnects when the underlying socket drops.
+pub struct ReconnectingWebSocket {
+    connection: Connection,
+    backoff: Backoff,
+    state: State,
+    /// Held here while there is no socket to record from.
+    recorder: Option<Recorder>,
+}
+
+impl ReconnectingWebSocket {
+    pub async fn connect(connection: Connection, backoff: Backoff) -> Result<Self> {
+        let ws = connection.clone().connect().await?;
+        Ok(Self {
+            connection,
+            backoff,
+            state: State::Connected(ws),
+            recorder: None,
+        })
+    }
+
+    /// Records every frame received from now on, across reconnections.
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        match &mut self.state {
+            State::Connected(ws) => ws.recorder = Some(recorder),
+            _ => self.recorder = Some(recorder),
+        }
+        self
+    }
+
+    /// Stops recording, waiting until every frame received so far has been written.
+    pub async fn stop_recording(&mut self) -> Result<()> {
+        match &mut self.state {
+            State::Connected(ws) => ws.stop_recording().await,
+            _ => match self.recorder.take() {
+                Some(recorder) => Ok(recorder.finish().await?),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Replaces the streams listened to. They are also re-sent after every reconnection.
+    pub async fn subscribe(&mut self, events: Vec<StreamName>) -> Result<()> {
+        self.connection.events = events.clone();
+        match &mut self.state {
+            State::Connected(ws) => ws.subscribe(events).await,
+            // The pending attempt would resubscribe to the old streams, so start over
+            State::Reconnecting { attempt, .. } => {
+                let attempt = *attempt;
+                self.reconnect(attempt);
+                Ok(())
+            }
+            State::Closed => Err(Error::StreamClosed),
+        }
+    }
+
+    fn reconnect(&mut self, attempt: u32) {
+        let delay = self.backoff.delay(attempt);
+        info!("Reconnecting in {:?} (attempt {})", delay, attempt + 1);
+        let connection = self.connection.clone();
+        let future = async move {
+            tokio::time::sleep(delay).await;
+            connection.connect().await
+        }
+        .boxed();
+        self.state = State::Reconnecting { attempt, future };
+    }
+}
+
+impl Stream for ReconnectingWebSocket {
+    type Item = Result<ConnectionEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            match &mut self.state {
+                State::Connected(ws) => match ready!(ws.poll_next_unpin(cx)) {
+                    Some(Ok(message)) => {
+                        return Poll::Ready(Some(Ok(ConnectionEvent::Message(message))))
+                    }
+                    // A malformed message does not mean the connection is broken
+                    Some(Err(Error::Serde(e))) => return Poll::Ready(Some(Err(Error::Serde(e)))),
+                    Some(Err(e)) => {
+                        warn!("Connection dropped: {}", e);
+                        let recorder = ws.recorder.take();
+                        self.recorder = recorder;
+                        self.reconnect(0);
+                        return Poll::Ready(Some(Ok(ConnectionEvent::Disconnected)));
+                    }
+                    None => {
+                        warn!("Connection closed by server");
+                        let recorder = ws.recorder.take();
+                        self.recorder = recorder;
+                        self.reconnect(0);
+                        return Poll::Ready(Some(Ok(ConnectionEvent::Disconnected)));
+                    }
+                },
+                State::Reconnecting { attempt, future } => {
+                    let attempt = *attempt;
+                    match ready!(future.as_mut().poll(cx)) {
+                        Ok(mut ws) => {
+                            info!("Reconnected");
+                            ws.recorder = self.recorder.take();
+                            self.state = State::Connected(ws);
+                            return Poll::Ready(Some(Ok(ConnectionEvent::Reconnected {
+                                attempts: attempt + 1,
+                            })));
+                        }
+                        Err(e) => {
+                            warn!("Reconnection attempt {} failed: {}", attempt + 1, e);
+                            if self
+                                .backoff
+                                .max_retries
+                                .is_some_and(|max| attempt + 1 >= max)
+                            {
+                                self.state = State::Closed;
+                                return Poll::Ready(Some(Err(e)));
+                            }
+                            self.reconnect(attempt + 1);
+                        }
+                    }
+                }
+                State::Closed => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stream::test::{connection, handshake};
+    use futures::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[test]
+    fn backoff_delay() {
+        let backoff = Backoff::new()
+            .initial_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_secs(1));
+        for _ in 0..100 {
+            let first = backoff.delay(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = backoff.delay(2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            let capped = backoff.delay(40);
+            assert!(capped >= Duration::from_millis(500) && capped <= Duration::from_secs(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let (con_tx, con_rx) = futures_channel::oneshot::channel();
+        tokio::spawn(async move {
+            let listener = TcpListener::bind("127.0.0.1:12347").await.unwrap();
+            con_tx.send(()).unwrap();
+
+            let (connection, _) = listener.accept().await.unwrap();
+            let mut connection = accept_async(connection).await.unwrap();
+            handshake(&mut connection, r#"["trade_updates"]"#).await;
+            // The client changes its subscription, then the connection drops
+            connection.next().await.unwrap().unwrap();
+            connection
+                .send(Message::text(
+                    r#"{"stream":"listening","data":{"streams":["account_updates"]}}"#,
+                ))
+                .await
+                .unwrap();
+            drop(connection);
+
+            let (connection, _) = listener.accept().await.unwrap();
+            let mut connection = accept_async(connection).await.unwrap();
+            handshake(&mut connection, r#"["account_updates"]"#).await;
+            connection
+                .send(Message::text(
+                    r#"{"stream":"listening","data":{"streams":["account_updates"]}}"#,
+                ))
+                .await
+                .unwrap();
+        });
+
+        con_rx.await.expect("Server not ready");
+        let backoff = Backoff::new()
+            .initial_delay(Duration::from_millis(10))
+            .max_retries(5);
+        let mut ws = ReconnectingWebSocket::connect(connection("ws://localhost:12347"), backoff)
+            .await
+            .unwrap();
+        ws.subscribe(vec![StreamName::AccountUpdates])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            ConnectionEvent::Disconnected
+        );
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            ConnectionEvent::Reconnected { attempts: 1 }
+        );
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            ConnectionEvent::Message(AlpacaMessage::Listening {
+                streams: vec![StreamName::AccountUpdates]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_while_reconnecting() {
+        let (con_tx, con_rx) = futures_channel::oneshot::channel();
+        tokio::spawn(async move {
+            let listener = TcpListener::bind("127.0.0.1:12354").await.unwrap();
+            con_tx.send(()).unwrap();
+
+            let (connection, _) = listener.accept().await.unwrap();
+            let mut connection = accept_async(connection).await.unwrap();
+            handshake(&mut connection, r#"["trade_updates"]"#).await;
+            drop(connection);
+
+            // The handshake fails unless the new subscription is sent
+            let (connection, _) = listener.accept().await.unwrap();
+            let mut connection = accept_async(connection).await.unwrap();
+            handshake(&mut connection, r#"["account_updates"]"#).await;
+            connection
+                .send(Message::text(
+                    r#"{"stream":"listening","data":{"streams":["account_updates"]}}"#,
+                ))
+                .await
+                .unwrap();
+        });
+
+        con_rx.await.expect("Server not ready");
+        let backoff = Backoff::new()
+            .initial_delay(Duration::from_millis(50))
+            .max_retries(1);
+        let mut ws = ReconnectingWebSocket::connect(connection("ws://localhost:12354"), backoff)
+            .await
+            .unwrap();
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            ConnectionEvent::Disconnected
+        );
+        ws.subscribe(vec![StreamName::AccountUpdates])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            ConnectionEvent::Reconnected { attempts: 1 }
+        );
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            ConnectionEvent::Message(AlpacaMessage::Listening {
+                streams: vec![StreamName::AccountUpdates]
+            })
+        );
+    }
+}
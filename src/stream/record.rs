@@ -0,0 +1,254 @@
+use crate::errors::{Error, Result};
+use crate::stream::AlpacaMessage;
+use chrono::{DateTime, Utc};
+use futures::{ready, Future, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::{self, JoinHandle};
+use tokio::time::{sleep_until, Instant, Sleep};
+
+/// A frame as received from the server, one per line of a recording.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    pub received_at: DateTime<Utc>,
+    pub frame: String,
+}
+
+/// Writes every frame received by a [`WebSocket`](crate::stream::WebSocket), or by a
+/// [`ReconnectingWebSocket`](crate::stream::reconnect::ReconnectingWebSocket) across all of its
+/// connections, as newline-delimited JSON. Frames are written on a blocking task so that disk I/O
+/// never stalls the stream, and each line is flushed as soon as it is written, so a recording is
+/// usable even if the process dies.
+pub struct Recorder {
+    frames: mpsc::UnboundedSender<RecordedFrame>,
+    writer: JoinHandle<io::Result<()>>,
+}
+
+impl Recorder {
+    /// Spawns the writer task, so this must be called from within a tokio runtime.
+    pub fn new<W: Write + Send + 'static>(mut writer: W) -> Self {
+        let (frames, mut receiver) = mpsc::unbounded_channel::<RecordedFrame>();
+        let writer = task::spawn_blocking(move || {
+            while let Some(frame) = receiver.blocking_recv() {
+                serde_json::to_writer(&mut writer, &frame)?;
+                writer.write_all(b"\n")?;
+                writer.flush()?;
+            }
+            Ok(())
+        });
+        Self { frames, writer }
+    }
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Queues `frame` for writing. Fails if the writer task stopped, in which case
+    /// [`finish`](Self::finish) returns the reason.
+    pub fn record(&self, frame: &str) -> io::Result<()> {
+        let recorded = RecordedFrame {
+            received_at: Utc::now(),
+            frame: frame.to_string(),
+        };
+        self.frames
+            .send(recorded)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "recorder has stopped"))
+    }
+
+    /// Waits until every queued frame has been written.
+    pub async fn finish(self) -> io::Result<()> {
+        let Self { frames, writer } = self;
+        drop(frames);
+        writer.await?
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pacing {
+    /// Yield frames with the same spacing as they were received.
+    RealTime,
+    AsFastAsPossible,
+}
+
+/// Replays a recording as a stream of messages, just like the [`WebSocket`](crate::stream::WebSocket)
+/// it was recorded from.
+pub struct Replay {
+    frames: VecDeque<RecordedFrame>,
+    pacing: Pacing,
+    /// When the first frame was yielded, and when it was originally received.
+    started: Option<(Instant, DateTime<Utc>)>,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl Replay {
+    #[allow(clippy::result_large_err)]
+    pub fn new<R: BufRead>(reader: R, pacing: Pacing) -> Result<Self> {
+        let frames = reader
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            frames,
+            pacing,
+            started: None,
+            delay: None,
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn open<P: AsRef<Path>>(path: P, pacing: Pacing) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?), pacing)
+    }
+}
+
+impl Stream for Replay {
+    type Item = Result<AlpacaMessage>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let received_at = match this.frames.front() {
+            Some(frame) => frame.received_at,
+            None => return Poll::Ready(None),
+        };
+        if this.pacing == Pacing::RealTime {
+            let (start, first_received_at) = *this
+                .started
+                .get_or_insert_with(|| (Instant::now(), received_at));
+            let due = start
+                + (received_at - first_received_at)
+                    .to_std()
+                    .unwrap_or_default();
+            if due > Instant::now() {
+                let delay = this.delay.get_or_insert_with(|| Box::pin(sleep_until(due)));
+                ready!(delay.as_mut().poll(cx));
+            }
+            this.delay = None;
+        }
+        let frame = this.frames.pop_front().expect("checked above");
+        Poll::Ready(Some(
+            serde_json::from_str(&frame.frame).map_err(Error::from),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stream::reconnect::{Backoff, ConnectionEvent, ReconnectingWebSocket};
+    use crate::stream::test::{connection, handshake, serve};
+    use crate::stream::StreamName;
+    use futures::{SinkExt, StreamExt, TryStreamExt};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+    use tokio_tungstenite::tungstenite::Message;
+
+    const LISTENING: &str = r#"{"stream":"listening","data":{"streams":["account_updates"]}}"#;
+    const ACCOUNT_UPDATE: &str = r#"{"stream":"account_updates","data":{"id":"ef505a9a-2f3c-4b8a-be95-6b6f185f8a03","created_at":"2018-02-26T19:22:31Z","updated_at":"2018-02-27T18:16:24Z","deleted_at":null,"status":"ACTIVE","currency":"USD","cash":"1241.54","cash_withdrawable":"523.71"}}"#;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        serve("127.0.0.1:12353", |mut stream| async move {
+            stream.send(Message::text(LISTENING)).await.unwrap();
+            stream
+                .send(Message::binary(ACCOUNT_UPDATE.as_bytes()))
+                .await
+                .unwrap();
+        })
+        .await;
+
+        let path = std::env::temp_dir().join("alpaca_test_record_and_replay.ndjson");
+        let mut ws = connection("ws://localhost:12353")
+            .connect()
+            .await
+            .unwrap()
+            .record(Recorder::create(&path).unwrap());
+        let live: Vec<AlpacaMessage> = ws.by_ref().take(2).try_collect().await.unwrap();
+        ws.stop_recording().await.unwrap();
+
+        let replay = Replay::open(&path, Pacing::AsFastAsPossible).unwrap();
+        let replayed: Vec<AlpacaMessage> = replay.try_collect().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replayed, live);
+        assert_eq!(
+            replayed[0],
+            AlpacaMessage::Listening {
+                streams: vec![StreamName::AccountUpdates]
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_across_reconnects() {
+        let (con_tx, con_rx) = futures_channel::oneshot::channel();
+        tokio::spawn(async move {
+            let listener = TcpListener::bind("127.0.0.1:12355").await.unwrap();
+            con_tx.send(()).unwrap();
+            for _ in 0..2 {
+                let (connection, _) = listener.accept().await.unwrap();
+                let mut connection = accept_async(connection).await.unwrap();
+                handshake(&mut connection, r#"["trade_updates"]"#).await;
+                connection
+                    .send(Message::text(ACCOUNT_UPDATE))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        con_rx.await.expect("Server not ready");
+        let path = std::env::temp_dir().join("alpaca_test_record_across_reconnects.ndjson");
+        let backoff = Backoff::new()
+            .initial_delay(Duration::from_millis(10))
+            .max_retries(5);
+        let mut ws = ReconnectingWebSocket::connect(connection("ws://localhost:12355"), backoff)
+            .await
+            .unwrap()
+            .record(Recorder::create(&path).unwrap());
+        let events: Vec<ConnectionEvent> = ws.by_ref().take(4).try_collect().await.unwrap();
+        assert_eq!(events[1], ConnectionEvent::Disconnected);
+        assert_eq!(events[2], ConnectionEvent::Reconnected { attempts: 1 });
+        ws.stop_recording().await.unwrap();
+
+        let replayed: Vec<AlpacaMessage> = Replay::open(&path, Pacing::AsFastAsPossible)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let live: Vec<ConnectionEvent> =
+            replayed.into_iter().map(ConnectionEvent::Message).collect();
+        assert_eq!(live, vec![events[0].clone(), events[3].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_real_time_pacing() {
+        let recording = format!(
+            "{}\n{}\n",
+            serde_json::to_string(&RecordedFrame {
+                received_at: "2021-03-16T18:38:01.000Z".parse().unwrap(),
+                frame: LISTENING.into(),
+            })
+            .unwrap(),
+            serde_json::to_string(&RecordedFrame {
+                received_at: "2021-03-16T18:38:01.200Z".parse().unwrap(),
+                frame: ACCOUNT_UPDATE.into(),
+            })
+            .unwrap()
+        );
+        let mut replay = Replay::new(recording.as_bytes(), Pacing::RealTime).unwrap();
+
+        let start = Instant::now();
+        replay.next().await.unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_millis(200));
+        replay.next().await.unwrap().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert!(replay.next().await.is_none());
+    }
+}
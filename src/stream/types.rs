@@ -1,7 +1,8 @@
-use crate::common::Order;
+use crate::common::{AccountUpdate, Order, OrderStatus};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum AlpacaData {
@@ -11,11 +12,18 @@ pub enum AlpacaData {
     Auth { key_id: String, secret_key: String },
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamName {
+    TradeUpdates,
+    AccountUpdates,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "action", content = "data")]
 pub enum AlpacaAction {
     #[serde(rename = "listen")]
-    Listen { streams: Vec<String> },
+    Listen { streams: Vec<StreamName> },
     #[serde(rename = "authenticate")]
     Authenticate { key_id: String, secret_key: String },
 }
@@ -34,16 +42,8 @@ pub enum Event {
     Fill {
         price: Decimal,
         timestamp: DateTime<Utc>,
-        #[serde(
-            deserialize_with = "crate::utils::from_str",
-            serialize_with = "crate::utils::to_string"
-        )]
-        qty: isize,
-        #[serde(
-            deserialize_with = "crate::utils::from_str",
-            serialize_with = "crate::utils::to_string"
-        )]
-        position_qty: isize,
+        qty: Decimal,
+        position_qty: Decimal,
     },
     New,
     OrderCancelRejected,
@@ -51,16 +51,8 @@ pub enum Event {
     PartialFill {
         price: Decimal,
         timestamp: DateTime<Utc>,
-        #[serde(
-            deserialize_with = "crate::utils::from_str",
-            serialize_with = "crate::utils::to_string"
-        )]
-        qty: isize,
-        #[serde(
-            deserialize_with = "crate::utils::from_str",
-            serialize_with = "crate::utils::to_string"
-        )]
-        position_qty: isize,
+        qty: Decimal,
+        position_qty: Decimal,
     },
     PendingCancel,
     PendingNew,
@@ -75,6 +67,114 @@ pub enum Event {
     Suspended,
 }
 
+/// The kind of an [`Event`], without its payload.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Calculated,
+    Canceled,
+    DoneForDay,
+    Expired,
+    Fill,
+    New,
+    OrderCancelRejected,
+    OrderReplaceRejected,
+    PartialFill,
+    PendingCancel,
+    PendingNew,
+    PendingReplace,
+    Rejected,
+    Replaced,
+    Stopped,
+    Suspended,
+}
+
+/// An event that cannot follow the order's current status, e.g. because events were dropped or
+/// arrived out of order.
+#[derive(Error, Clone, Copy, Debug, PartialEq, Eq)]
+#[error("Invalid {event:?} event for order with status {from:?}")]
+pub struct InvalidTransition {
+    pub from: OrderStatus,
+    pub event: EventKind,
+}
+
+impl Event {
+    /// The status an order has after this event. Rejected cancel and replace requests leave the
+    /// status unchanged, so `None` is returned for them.
+    pub fn status(&self) -> Option<OrderStatus> {
+        match self {
+            Event::Calculated => Some(OrderStatus::Calculated),
+            Event::Canceled { .. } => Some(OrderStatus::Canceled),
+            Event::DoneForDay => Some(OrderStatus::DoneForDay),
+            Event::Expired { .. } => Some(OrderStatus::Expired),
+            Event::Fill { .. } => Some(OrderStatus::Filled),
+            Event::New => Some(OrderStatus::New),
+            Event::OrderCancelRejected | Event::OrderReplaceRejected => None,
+            Event::PartialFill { .. } => Some(OrderStatus::PartiallyFilled),
+            Event::PendingCancel => Some(OrderStatus::PendingCancel),
+            Event::PendingNew => Some(OrderStatus::PendingNew),
+            Event::PendingReplace => Some(OrderStatus::PendingReplace),
+            Event::Rejected { .. } => Some(OrderStatus::Rejected),
+            Event::Replaced { .. } => Some(OrderStatus::Replaced),
+            Event::Stopped => Some(OrderStatus::Stopped),
+            Event::Suspended => Some(OrderStatus::Suspended),
+        }
+    }
+
+    /// Validates that this event can follow an order's current status, and returns the
+    /// resulting status.
+    ///
+    /// Rejected cancels and replaces are valid from any status, as they usually arrive after the
+    /// order was already filled.
+    pub fn transition(&self, from: OrderStatus) -> Result<OrderStatus, InvalidTransition> {
+        let valid = match self {
+            Event::OrderCancelRejected | Event::OrderReplaceRejected => true,
+            _ if from.is_terminal() => false,
+            Event::PendingNew => matches!(
+                from,
+                OrderStatus::Accepted | OrderStatus::AcceptedForBidding | OrderStatus::PendingNew
+            ),
+            Event::New => matches!(
+                from,
+                OrderStatus::Accepted
+                    | OrderStatus::AcceptedForBidding
+                    | OrderStatus::PendingNew
+                    | OrderStatus::New
+            ),
+            _ => true,
+        };
+        if valid {
+            Ok(self.status().unwrap_or(from))
+        } else {
+            Err(InvalidTransition {
+                from,
+                event: self.kind(),
+            })
+        }
+    }
+
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Calculated => EventKind::Calculated,
+            Event::Canceled { .. } => EventKind::Canceled,
+            Event::DoneForDay => EventKind::DoneForDay,
+            Event::Expired { .. } => EventKind::Expired,
+            Event::Fill { .. } => EventKind::Fill,
+            Event::New => EventKind::New,
+            Event::OrderCancelRejected => EventKind::OrderCancelRejected,
+            Event::OrderReplaceRejected => EventKind::OrderReplaceRejected,
+            Event::PartialFill { .. } => EventKind::PartialFill,
+            Event::PendingCancel => EventKind::PendingCancel,
+            Event::PendingNew => EventKind::PendingNew,
+            Event::PendingReplace => EventKind::PendingReplace,
+            Event::Rejected { .. } => EventKind::Rejected,
+            Event::Replaced { .. } => EventKind::Replaced,
+            Event::Stopped => EventKind::Stopped,
+            Event::Suspended => EventKind::Suspended,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct OrderEvent {
@@ -99,29 +199,22 @@ pub enum AlpacaMessage {
         action: String,
     },
     Listening {
-        streams: Vec<String>,
+        streams: Vec<StreamName>,
     },
     TradeUpdates(OrderEvent),
-    AccountUpdates {
-        id: String,
-        created_at: String,
-        updated_at: String,
-        deleted_at: Option<String>,
-        status: String,
-        currency: String,
-        cash: Decimal,
-        cash_withdrawable: Decimal,
-    },
+    AccountUpdates(AccountUpdate),
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::common::AccountStatus;
+    use chrono::TimeZone;
 
     #[test]
     fn serde_listen() {
         let serialized = serde_json::to_string(&AlpacaAction::Listen {
-            streams: vec!["trade_updates".into(), "account_updates".into()],
+            streams: vec![StreamName::TradeUpdates, StreamName::AccountUpdates],
         })
         .unwrap();
         assert_eq!(serialized, "{\"action\":\"listen\",\"data\":{\"streams\":[\"trade_updates\",\"account_updates\"]}}");
@@ -133,4 +226,73 @@ mod test {
         let deserialized: AlpacaMessage = serde_json::from_str(json).unwrap();
         let _serialized = serde_json::to_string(&deserialized).unwrap();
     }
+
+    #[test]
+    fn serde_account_update() {
+        let json = r#"{"stream":"account_updates","data":{"id":"ef505a9a-2f3c-4b8a-be95-6b6f185f8a03","created_at":"2018-02-26T19:22:31Z","updated_at":"2018-02-27T18:16:24Z","deleted_at":null,"status":"ACTIVE","currency":"USD","cash":"1241.54","cash_withdrawable":"523.71"}}"#;
+        let deserialized: AlpacaMessage = serde_json::from_str(json).unwrap();
+        match deserialized {
+            AlpacaMessage::AccountUpdates(update) => {
+                assert_eq!(update.status, AccountStatus::Active);
+                assert_eq!(
+                    update.updated_at,
+                    Utc.with_ymd_and_hms(2018, 2, 27, 18, 16, 24).unwrap()
+                );
+                assert_eq!(update.deleted_at, None);
+            }
+            other => panic!("Expected account update, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn order_status_predicates() {
+        assert!(OrderStatus::Filled.is_terminal());
+        assert!(OrderStatus::DoneForDay.is_open());
+        assert!(OrderStatus::PendingReplace.can_cancel());
+        assert!(!OrderStatus::PendingCancel.can_cancel());
+        assert!(OrderStatus::PartiallyFilled.can_replace());
+        assert!(!OrderStatus::PendingNew.can_replace());
+    }
+
+    #[test]
+    fn transitions() {
+        let fill = Event::Fill {
+            price: Decimal::new(17908, 2),
+            timestamp: Utc.with_ymd_and_hms(2018, 2, 28, 20, 38, 22).unwrap(),
+            qty: Decimal::new(100, 0),
+            position_qty: Decimal::new(100, 0),
+        };
+        assert_eq!(
+            Event::New.transition(OrderStatus::PendingNew),
+            Ok(OrderStatus::New)
+        );
+        assert_eq!(
+            fill.transition(OrderStatus::PartiallyFilled),
+            Ok(OrderStatus::Filled)
+        );
+        assert_eq!(
+            Event::OrderCancelRejected.transition(OrderStatus::PendingCancel),
+            Ok(OrderStatus::PendingCancel)
+        );
+        // The cancel was rejected because the order filled in the meantime
+        let filled = fill.transition(OrderStatus::PendingCancel).unwrap();
+        assert_eq!(
+            Event::OrderCancelRejected.transition(filled),
+            Ok(OrderStatus::Filled)
+        );
+        assert_eq!(
+            Event::OrderReplaceRejected.transition(OrderStatus::Filled),
+            Ok(OrderStatus::Filled)
+        );
+        assert_eq!(
+            fill.transition(OrderStatus::Canceled),
+            Err(InvalidTransition {
+                from: OrderStatus::Canceled,
+                event: EventKind::Fill
+            })
+        );
+        assert!(Event::PendingNew
+            .transition(OrderStatus::PartiallyFilled)
+            .is_err());
+    }
 }
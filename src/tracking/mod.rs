@@ -0,0 +1,9 @@
+//! Local state kept in sync from the REST API and the trade stream.
+
+mod orders;
+mod positions;
+mod reconcile;
+
+pub use orders::*;
+pub use positions::*;
+pub use reconcile::*;
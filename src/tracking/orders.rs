@@ -0,0 +1,318 @@
+use crate::common::Order;
+use crate::errors::Result;
+use crate::rest::orders::{stream_orders, GetOrders, QueryOrderStatus};
+use crate::stream::{Event, InvalidTransition, OrderEvent};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use std::collections::HashMap;
+use tracing::warn;
+use uuid::Uuid;
+use vila::Client;
+
+/// How an [`OrderEvent`] relates to the orders already tracked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Applied {
+    /// The event updated a tracked order.
+    Updated,
+    /// The event introduced a newly submitted order.
+    Added,
+    /// The event is for an order that was never seen, e.g. because events were missed or the
+    /// tracker was seeded after the order was submitted. The order is tracked from now on.
+    UnknownOrder,
+    /// The event cannot follow the tracked order's status, so events were dropped or arrived out
+    /// of order. The order is updated to the state carried by the event, unless the tracked order
+    /// is already terminal, in which case the stale event is ignored.
+    InvalidTransition(InvalidTransition),
+}
+
+/// The latest known state of every order seen, keyed by id.
+#[derive(Clone, Debug, Default)]
+pub struct OrderTracker {
+    orders: HashMap<Uuid, Order>,
+    client_order_ids: HashMap<String, Uuid>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn from_orders<I: IntoIterator<Item = Order>>(orders: I) -> Self {
+        let mut tracker = Self::new();
+        for order in orders {
+            tracker.track(order);
+        }
+        tracker
+    }
+
+    /// Seeds a tracker with every currently open order.
+    pub async fn seed(client: &Client) -> Result<Self> {
+        let request = GetOrders {
+            status: QueryOrderStatus::Open,
+            ..GetOrders::new()
+        };
+        let orders: Vec<Order> = stream_orders(client, request).try_collect().await?;
+        Ok(Self::from_orders(orders))
+    }
+
+    /// Tracks `order`, replacing any previously known state of it.
+    pub fn track(&mut self, order: Order) {
+        self.client_order_ids
+            .insert(order.client_order_id.clone(), order.id);
+        self.orders.insert(order.id, order);
+    }
+
+    pub fn apply(&mut self, update: &OrderEvent) -> Applied {
+        let order = &update.order;
+        let applied = if let Some(tracked) = self.orders.get(&order.id) {
+            match update.event.transition(tracked.status) {
+                Ok(_) => Applied::Updated,
+                Err(e) if tracked.status.is_terminal() => {
+                    warn!("{} {}, ignoring it", e, order.id);
+                    return Applied::InvalidTransition(e);
+                }
+                Err(e) => {
+                    warn!("{} {}", e, order.id);
+                    Applied::InvalidTransition(e)
+                }
+            }
+        } else if matches!(update.event, Event::New | Event::PendingNew) {
+            Applied::Added
+        } else {
+            warn!(
+                "Received {:?} event for unknown order {}",
+                update.event.kind(),
+                order.id
+            );
+            Applied::UnknownOrder
+        };
+        // The replacement's events may arrive before the `replaced` event of the original
+        if let Some(replaced) = order.replaces.and_then(|id| self.orders.get_mut(&id)) {
+            replaced.replaced_by.get_or_insert(order.id);
+        }
+        self.track(order.clone());
+        applied
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<&Order> {
+        self.orders.get(id)
+    }
+
+    pub fn get_by_client_order_id(&self, client_order_id: &str) -> Option<&Order> {
+        self.client_order_ids
+            .get(client_order_id)
+            .and_then(|id| self.orders.get(id))
+    }
+
+    /// Follows the chain of replacements starting at `id` and returns the newest order in it.
+    pub fn latest(&self, id: &Uuid) -> Option<&Order> {
+        let mut order = self.orders.get(id)?;
+        while let Some(next) = order.replaced_by.and_then(|id| self.orders.get(&id)) {
+            order = next;
+        }
+        Some(order)
+    }
+
+    /// Stops tracking terminal orders last updated before `before`, returning how many were
+    /// removed. Recently closed orders are kept so that late events for them are still
+    /// recognised.
+    pub fn prune_terminal(&mut self, before: DateTime<Utc>) -> usize {
+        let pruned: Vec<Uuid> = self
+            .orders
+            .values()
+            .filter(|order| {
+                order.status.is_terminal() && order.updated_at.unwrap_or(order.created_at) < before
+            })
+            .map(|order| order.id)
+            .collect();
+        for id in &pruned {
+            if let Some(order) = self.orders.remove(id) {
+                if self.client_order_ids.get(&order.client_order_id) == Some(id) {
+                    self.client_order_ids.remove(&order.client_order_id);
+                }
+            }
+        }
+        pruned.len()
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values().filter(|order| order.status.is_open())
+    }
+
+    pub fn open_orders_for_symbol<'a>(
+        &'a self,
+        symbol: &'a str,
+    ) -> impl Iterator<Item = &'a Order> {
+        self.open_orders()
+            .filter(move |order| order.symbol == symbol)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::client_with_url;
+    use crate::common::test::ORDER;
+    use crate::common::OrderStatus;
+    use crate::stream::EventKind;
+    use mockito::mock;
+    use rust_decimal::Decimal;
+
+    pub(crate) fn order(
+        id: &str,
+        client_order_id: &str,
+        symbol: &str,
+        status: OrderStatus,
+    ) -> Order {
+        let mut order: Order = serde_json::from_str(ORDER).unwrap();
+        order.id = id.parse().unwrap();
+        order.client_order_id = client_order_id.to_string();
+        order.symbol = symbol.to_string();
+        order.status = status;
+        order
+    }
+
+    fn timestamp() -> DateTime<Utc> {
+        "2021-03-16T18:38:02Z".parse().unwrap()
+    }
+
+    const FIRST: &str = "61e69015-8549-4bfd-b9c3-01e75843f47d";
+    const SECOND: &str = "9bd4ba4b-d7cf-4c43-8c09-1b5a7e5f4a5d";
+    const THIRD: &str = "0dd0e06b-8e89-4c5e-9f6a-6c3e2a0f1ab3";
+
+    #[test]
+    fn apply_events() {
+        let mut tracker =
+            OrderTracker::from_orders(vec![order(FIRST, "a", "AAPL", OrderStatus::New)]);
+
+        let new = OrderEvent {
+            event: Event::New,
+            order: order(SECOND, "b", "MSFT", OrderStatus::New),
+        };
+        assert_eq!(tracker.apply(&new), Applied::Added);
+        assert_eq!(tracker.open_orders().count(), 2);
+
+        let fill = OrderEvent {
+            event: Event::Fill {
+                price: Decimal::new(24623, 2),
+                timestamp: timestamp(),
+                qty: Decimal::new(100, 0),
+                position_qty: Decimal::new(100, 0),
+            },
+            order: order(SECOND, "b", "MSFT", OrderStatus::Filled),
+        };
+        assert_eq!(tracker.apply(&fill), Applied::Updated);
+        assert_eq!(
+            tracker.get_by_client_order_id("b").unwrap().status,
+            OrderStatus::Filled
+        );
+        assert_eq!(tracker.open_orders_for_symbol("MSFT").count(), 0);
+        assert_eq!(tracker.open_orders_for_symbol("AAPL").count(), 1);
+
+        let unknown = OrderEvent {
+            event: Event::Canceled {
+                timestamp: timestamp(),
+            },
+            order: order(THIRD, "c", "AAPL", OrderStatus::Canceled),
+        };
+        assert_eq!(tracker.apply(&unknown), Applied::UnknownOrder);
+        assert!(tracker.get(&THIRD.parse().unwrap()).is_some());
+
+        let late_new = OrderEvent {
+            event: Event::New,
+            order: order(SECOND, "b", "MSFT", OrderStatus::New),
+        };
+        assert_eq!(
+            tracker.apply(&late_new),
+            Applied::InvalidTransition(InvalidTransition {
+                from: OrderStatus::Filled,
+                event: EventKind::New,
+            })
+        );
+        // The filled order is not reopened by the late event
+        assert_eq!(
+            tracker.get_by_client_order_id("b").unwrap().status,
+            OrderStatus::Filled
+        );
+        assert_eq!(tracker.open_orders_for_symbol("MSFT").count(), 0);
+    }
+
+    #[test]
+    fn replaced_chain() {
+        let first = FIRST.parse().unwrap();
+        let mut tracker =
+            OrderTracker::from_orders(vec![order(FIRST, "a", "AAPL", OrderStatus::New)]);
+
+        // The replacement is announced before the original is marked as replaced
+        let mut replacement = order(SECOND, "b", "AAPL", OrderStatus::New);
+        replacement.replaces = Some(first);
+        tracker.apply(&OrderEvent {
+            event: Event::New,
+            order: replacement,
+        });
+        assert_eq!(tracker.latest(&first).unwrap().client_order_id, "b");
+
+        let mut replaced = order(FIRST, "a", "AAPL", OrderStatus::Replaced);
+        replaced.replaced_by = Some(SECOND.parse().unwrap());
+        tracker.apply(&OrderEvent {
+            event: Event::Replaced {
+                timestamp: timestamp(),
+            },
+            order: replaced,
+        });
+        let mut second_replacement = order(THIRD, "c", "AAPL", OrderStatus::New);
+        second_replacement.replaces = Some(SECOND.parse().unwrap());
+        tracker.apply(&OrderEvent {
+            event: Event::New,
+            order: second_replacement,
+        });
+
+        assert_eq!(tracker.latest(&first).unwrap().client_order_id, "c");
+        let open: Vec<&str> = tracker
+            .open_orders()
+            .map(|order| order.client_order_id.as_str())
+            .collect();
+        assert_eq!(open.len(), 2);
+        assert!(!open.contains(&"a"));
+    }
+
+    #[test]
+    fn prune_terminal() {
+        let mut filled = order(FIRST, "a", "AAPL", OrderStatus::Filled);
+        filled.updated_at = Some("2021-03-15T20:00:00Z".parse().unwrap());
+        let mut canceled = order(SECOND, "b", "AAPL", OrderStatus::Canceled);
+        canceled.updated_at = Some(timestamp());
+        let mut tracker = OrderTracker::from_orders(vec![
+            filled,
+            canceled,
+            order(THIRD, "c", "AAPL", OrderStatus::New),
+        ]);
+
+        assert_eq!(
+            tracker.prune_terminal("2021-03-16T00:00:00Z".parse().unwrap()),
+            1
+        );
+        assert!(tracker.get(&FIRST.parse().unwrap()).is_none());
+        assert!(tracker.get_by_client_order_id("a").is_none());
+        assert!(tracker.get_by_client_order_id("b").is_some());
+        assert_eq!(tracker.open_orders().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_seed() {
+        let _m = mock("GET", "/orders")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(mockito::Matcher::UrlEncoded("status".into(), "open".into()))
+            .with_body(format!("[{}]", ORDER))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let tracker = OrderTracker::seed(&client).await.unwrap();
+        assert_eq!(tracker.open_orders().count(), 1);
+        assert!(tracker
+            .get_by_client_order_id("eb9e2aaa-f71a-4f51-b5b4-52a6c565dad4")
+            .is_some());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::common::Side;
+use crate::errors::Result;
+use crate::rest::positions::{self, GetPositions, Position};
+use crate::stream::{Event, OrderEvent};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tracing::warn;
+use vila::Client;
+
+/// A position as maintained locally. Short positions have a negative `qty`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookPosition {
+    pub symbol: String,
+    pub qty: Decimal,
+    pub avg_entry_price: Decimal,
+}
+
+impl From<Position> for BookPosition {
+    fn from(position: Position) -> Self {
+        let qty = match position.side {
+            positions::Side::Long => position.qty,
+            positions::Side::Short => -position.qty.abs(),
+        };
+        Self {
+            symbol: position.symbol,
+            qty,
+            avg_entry_price: position.avg_entry_price,
+        }
+    }
+}
+
+/// Raised when the quantity computed from fills disagrees with the `position_qty` reported by
+/// the broker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DriftAlert {
+    pub symbol: String,
+    pub local_qty: Decimal,
+    pub broker_qty: Decimal,
+}
+
+/// Positions by symbol, kept up to date by fills.
+#[derive(Clone, Debug, Default)]
+pub struct PositionBook {
+    positions: HashMap<String, BookPosition>,
+}
+
+impl PositionBook {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn from_positions<I: IntoIterator<Item = Position>>(positions: I) -> Self {
+        Self {
+            positions: positions
+                .into_iter()
+                .map(|position| (position.symbol.clone(), position.into()))
+                .collect(),
+        }
+    }
+
+    pub async fn seed(client: &Client) -> Result<Self> {
+        let positions = client.send(&GetPositions).await?;
+        Ok(Self::from_positions(positions))
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&BookPosition> {
+        self.positions.get(symbol)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &BookPosition> {
+        self.positions.values()
+    }
+
+    /// Replaces the position of `position.symbol`, removing it if its quantity is zero.
+    pub fn track(&mut self, position: BookPosition) {
+        if position.qty.is_zero() {
+            self.positions.remove(&position.symbol);
+        } else {
+            self.positions.insert(position.symbol.clone(), position);
+        }
+    }
+
+    /// Applies a fill to the position of the order's symbol. Other events are ignored.
+    ///
+    /// If the resulting quantity disagrees with the broker's `position_qty`, the broker's
+    /// quantity is adopted and a `DriftAlert` is returned. When the two also disagree on the side
+    /// of the position, the computed entry price is meaningless, so the fill price stands in for
+    /// it until the position is replaced via [`track`](Self::track), e.g. from `GetPosition`.
+    pub fn apply(&mut self, update: &OrderEvent) -> Option<DriftAlert> {
+        let (price, qty, position_qty) = match &update.event {
+            Event::Fill {
+                price,
+                qty,
+                position_qty,
+                ..
+            }
+            | Event::PartialFill {
+                price,
+                qty,
+                position_qty,
+                ..
+            } => (*price, *qty, *position_qty),
+            _ => return None,
+        };
+        let symbol = &update.order.symbol;
+        let delta = match update.order.side {
+            Side::Buy => qty,
+            Side::Sell => -qty,
+        };
+        let position = self
+            .positions
+            .entry(symbol.clone())
+            .or_insert_with(|| BookPosition {
+                symbol: symbol.clone(),
+                qty: Decimal::ZERO,
+                avg_entry_price: Decimal::ZERO,
+            });
+        let new_qty = position.qty + delta;
+        if new_qty.is_zero() {
+            position.avg_entry_price = Decimal::ZERO;
+        } else if position.qty.is_zero()
+            || position.qty.is_sign_positive() == delta.is_sign_positive()
+        {
+            // Adding to the position
+            position.avg_entry_price = (position.qty.abs() * position.avg_entry_price
+                + delta.abs() * price)
+                / new_qty.abs();
+        } else if new_qty.is_sign_positive() != position.qty.is_sign_positive() {
+            // Flipped from long to short or vice versa
+            position.avg_entry_price = price;
+        }
+        position.qty = new_qty;
+
+        let alert = if new_qty != position_qty {
+            warn!(
+                "Position drift for {}: computed {} but broker reports {}",
+                symbol, new_qty, position_qty
+            );
+            if new_qty.is_zero() || new_qty.is_sign_positive() != position_qty.is_sign_positive() {
+                position.avg_entry_price = price;
+            }
+            position.qty = position_qty;
+            Some(DriftAlert {
+                symbol: symbol.clone(),
+                local_qty: new_qty,
+                broker_qty: position_qty,
+            })
+        } else {
+            None
+        };
+        if position.qty.is_zero() {
+            self.positions.remove(symbol);
+        }
+        alert
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use crate::common::OrderStatus;
+    use crate::tracking::orders::test::order;
+    use mockito::mock;
+
+    fn fill(side: Side, price: i64, qty: i64, position_qty: i64) -> OrderEvent {
+        let mut order = order(
+            "61e69015-8549-4bfd-b9c3-01e75843f47d",
+            "a",
+            "AAPL",
+            OrderStatus::Filled,
+        );
+        order.side = side;
+        OrderEvent {
+            event: Event::Fill {
+                price: Decimal::new(price, 0),
+                timestamp: "2021-03-16T18:38:02Z".parse().unwrap(),
+                qty: Decimal::new(qty, 0),
+                position_qty: Decimal::new(position_qty, 0),
+            },
+            order,
+        }
+    }
+
+    #[test]
+    fn apply_fills() {
+        let mut book = PositionBook::new();
+        assert_eq!(book.apply(&fill(Side::Buy, 100, 10, 10)), None);
+        assert_eq!(book.apply(&fill(Side::Buy, 130, 5, 15)), None);
+        assert_eq!(
+            book.get("AAPL").unwrap().avg_entry_price,
+            Decimal::new(110, 0)
+        );
+
+        // Reducing keeps the entry price
+        assert_eq!(book.apply(&fill(Side::Sell, 150, 5, 10)), None);
+        assert_eq!(
+            book.get("AAPL").unwrap(),
+            &BookPosition {
+                symbol: "AAPL".into(),
+                qty: Decimal::new(10, 0),
+                avg_entry_price: Decimal::new(110, 0),
+            }
+        );
+
+        // Flipping to short starts over at the fill price
+        assert_eq!(book.apply(&fill(Side::Sell, 140, 15, -5)), None);
+        let position = book.get("AAPL").unwrap();
+        assert_eq!(position.qty, Decimal::new(-5, 0));
+        assert_eq!(position.avg_entry_price, Decimal::new(140, 0));
+
+        assert_eq!(book.apply(&fill(Side::Buy, 120, 5, 0)), None);
+        assert!(book.get("AAPL").is_none());
+    }
+
+    #[test]
+    fn drift_alert() {
+        let mut book = PositionBook::new();
+        book.apply(&fill(Side::Buy, 100, 10, 10));
+        // A partial fill of 5 shares was missed
+        let alert = book.apply(&fill(Side::Buy, 100, 10, 25));
+        assert_eq!(
+            alert,
+            Some(DriftAlert {
+                symbol: "AAPL".into(),
+                local_qty: Decimal::new(20, 0),
+                broker_qty: Decimal::new(25, 0),
+            })
+        );
+        assert_eq!(book.get("AAPL").unwrap().qty, Decimal::new(25, 0));
+    }
+
+    #[test]
+    fn drift_changes_side() {
+        let mut book = PositionBook::new();
+        book.apply(&fill(Side::Buy, 100, 10, 10));
+        // Flat locally, but the broker still holds 5 shares
+        assert!(book.apply(&fill(Side::Sell, 150, 10, 5)).is_some());
+        assert_eq!(
+            book.get("AAPL").unwrap(),
+            &BookPosition {
+                symbol: "AAPL".into(),
+                qty: Decimal::new(5, 0),
+                avg_entry_price: Decimal::new(150, 0),
+            }
+        );
+
+        // Still long locally, but the broker went short
+        assert!(book.apply(&fill(Side::Sell, 140, 5, -5)).is_some());
+        assert_eq!(
+            book.get("AAPL").unwrap(),
+            &BookPosition {
+                symbol: "AAPL".into(),
+                qty: Decimal::new(-5, 0),
+                avg_entry_price: Decimal::new(140, 0),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seed() {
+        let _m = mock("GET", "/positions")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .with_body(
+                r#"[{
+                    "asset_id": "b28f4066-5c6d-479b-a2af-85dc1a8f16fb",
+                    "symbol": "SPY",
+                    "exchange": "ARCA",
+                    "asset_class": "us_equity",
+                    "avg_entry_price": "400.25",
+                    "qty": "-3",
+                    "side": "short",
+                    "market_value": "-1203.0",
+                    "cost_basis": "-1200.75",
+                    "unrealized_pl": "-2.25",
+                    "unrealized_plpc": "-0.0019",
+                    "unrealized_intraday_pl": "-2.25",
+                    "unrealized_intraday_plpc": "-0.0019",
+                    "current_price": "401.0",
+                    "lastday_price": "399.5",
+                    "change_today": "0.0038"
+                }]"#,
+            )
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let book = PositionBook::seed(&client).await.unwrap();
+        let position = book.get("SPY").unwrap();
+        assert_eq!(position.qty, Decimal::new(-3, 0));
+        assert_eq!(position.avg_entry_price, Decimal::new(40025, 2));
+    }
+}
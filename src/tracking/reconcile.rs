@@ -0,0 +1,363 @@
+use crate::common::{Order, OrderStatus};
+use crate::errors::{Error, Result};
+use crate::rest::account::GetAccount;
+use crate::rest::orders::{stream_orders, GetOrder, GetOrders, QueryOrderStatus};
+use crate::rest::positions::GetPositions;
+use crate::stream::reconnect::ConnectionEvent;
+use crate::stream::AlpacaMessage;
+use crate::tracking::{BookPosition, OrderTracker, PositionBook};
+use futures::{future, stream, Stream, StreamExt, TryStreamExt};
+use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::Duration;
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+use tracing::warn;
+use uuid::Uuid;
+use vila::Client;
+
+/// A difference between the broker's state and the locally maintained state. Each variant
+/// carries the broker's view, so it can be fed back into the local state to heal it.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discrepancy {
+    /// An open order that is not tracked locally.
+    MissingOrder {
+        order: Order,
+    },
+    /// `order` is the broker's state of an order whose tracked status is `local`.
+    StatusMismatch {
+        local: OrderStatus,
+        order: Order,
+    },
+    /// Quantities are signed, with short positions negative.
+    QtyMismatch {
+        symbol: String,
+        local: Decimal,
+        broker: BookPosition,
+    },
+    CashMismatch {
+        local: Decimal,
+        broker: Decimal,
+    },
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Trigger {
+    Event(Result<ConnectionEvent>),
+    Tick,
+    End,
+}
+
+/// Compares local state with the REST API, e.g. periodically or after the stream reconnected.
+pub struct Reconciler<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Reconciler<'a> {
+    pub fn new(client: &'a Client) -> Self {
+        Self { client }
+    }
+
+    /// Returns every discrepancy found. `cash` is the locally known cash balance, if any, e.g.
+    /// from the last account update.
+    pub async fn reconcile(
+        &self,
+        orders: &OrderTracker,
+        positions: &PositionBook,
+        cash: Option<Decimal>,
+    ) -> Result<Vec<Discrepancy>> {
+        let mut discrepancies = self.reconcile_orders(orders).await?;
+        discrepancies.extend(self.reconcile_positions(positions).await?);
+        if let Some(local) = cash {
+            let account = self.client.send(&GetAccount).await?;
+            if account.cash != local {
+                discrepancies.push(Discrepancy::CashMismatch {
+                    local,
+                    broker: account.cash,
+                });
+            }
+        }
+        Ok(discrepancies)
+    }
+
+    pub async fn reconcile_orders(&self, tracker: &OrderTracker) -> Result<Vec<Discrepancy>> {
+        let request = GetOrders {
+            status: QueryOrderStatus::Open,
+            ..GetOrders::new()
+        };
+        let open: Vec<Order> = stream_orders(self.client, request).try_collect().await?;
+        let open_ids: HashSet<Uuid> = open.iter().map(|order| order.id).collect();
+
+        let mut discrepancies = Vec::new();
+        for order in open {
+            match tracker.get(&order.id) {
+                None => discrepancies.push(Discrepancy::MissingOrder { order }),
+                Some(local) if local.status != order.status => {
+                    discrepancies.push(Discrepancy::StatusMismatch {
+                        local: local.status,
+                        order,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        // Orders still open locally that the broker has closed in the meantime. These are looked
+        // up by id, since listing closed orders would page through the whole order history
+        // whenever a long-lived order is tracked.
+        let closed = tracker
+            .open_orders()
+            .filter(|order| !open_ids.contains(&order.id));
+        for local in closed {
+            let id = local.id.to_string();
+            let order = self.client.send(&GetOrder::new(&id)).await?;
+            if order.status != local.status {
+                discrepancies.push(Discrepancy::StatusMismatch {
+                    local: local.status,
+                    order,
+                });
+            }
+        }
+        Ok(discrepancies)
+    }
+
+    /// Keeps `orders` and `positions` up to date from `events`, e.g. a
+    /// [`ReconnectingWebSocket`](crate::stream::reconnect::ReconnectingWebSocket), until they end.
+    ///
+    /// Reconciles every `period`, and as soon as the stream reconnected since updates may have
+    /// been missed while it was down. Any discrepancies found are passed to `on_discrepancies`
+    /// together with the state, so they can be healed. The cash balance is taken from account
+    /// updates.
+    pub async fn sync<S, F>(
+        &self,
+        events: S,
+        orders: &mut OrderTracker,
+        positions: &mut PositionBook,
+        period: Duration,
+        mut on_discrepancies: F,
+    ) -> Result<()>
+    where
+        S: Stream<Item = Result<ConnectionEvent>> + Unpin,
+        F: FnMut(&mut OrderTracker, &mut PositionBook, Vec<Discrepancy>),
+    {
+        let mut interval = interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let ticks = stream::unfold(interval, |mut interval| async move {
+            interval.tick().await;
+            Some((Trigger::Tick, interval))
+        });
+        let events = events
+            .map(Trigger::Event)
+            .chain(stream::once(future::ready(Trigger::End)));
+        let mut triggers = Box::pin(stream::select(events, ticks));
+
+        let mut cash = None;
+        while let Some(trigger) = triggers.next().await {
+            let due = match trigger {
+                Trigger::Tick => true,
+                Trigger::End => break,
+                Trigger::Event(event) => match event {
+                    Ok(ConnectionEvent::Message(AlpacaMessage::TradeUpdates(update))) => {
+                        orders.apply(&update);
+                        positions.apply(&update);
+                        false
+                    }
+                    Ok(ConnectionEvent::Message(AlpacaMessage::AccountUpdates(update))) => {
+                        cash = Some(update.cash);
+                        false
+                    }
+                    Ok(ConnectionEvent::Reconnected { .. }) => true,
+                    Ok(_) => false,
+                    Err(Error::Serde(e)) => {
+                        warn!("Skipping unparseable message: {}", e);
+                        false
+                    }
+                    Err(e) => return Err(e),
+                },
+            };
+            if due {
+                let discrepancies = self.reconcile(orders, positions, cash).await?;
+                if !discrepancies.is_empty() {
+                    on_discrepancies(orders, positions, discrepancies);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn reconcile_positions(&self, book: &PositionBook) -> Result<Vec<Discrepancy>> {
+        let broker: HashMap<String, BookPosition> = self
+            .client
+            .send(&GetPositions)
+            .await?
+            .into_iter()
+            .map(|position| (position.symbol.clone(), BookPosition::from(position)))
+            .collect();
+        let symbols: BTreeSet<&String> = broker
+            .keys()
+            .chain(book.positions().map(|position| &position.symbol))
+            .collect();
+
+        let discrepancies = symbols
+            .into_iter()
+            .filter_map(|symbol| {
+                let local = book
+                    .get(symbol)
+                    .map_or(Decimal::ZERO, |position| position.qty);
+                let broker = broker.get(symbol).cloned().unwrap_or_else(|| BookPosition {
+                    symbol: symbol.clone(),
+                    qty: Decimal::ZERO,
+                    avg_entry_price: Decimal::ZERO,
+                });
+                (local != broker.qty).then(|| Discrepancy::QtyMismatch {
+                    symbol: symbol.clone(),
+                    local,
+                    broker,
+                })
+            })
+            .collect();
+        Ok(discrepancies)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client_with_url;
+    use crate::common::test::ORDER;
+    use crate::rest::account::test::ACCOUNT;
+    use crate::rest::positions::test::POSITIONS;
+    use crate::rest::positions::Position;
+    use crate::tracking::orders::test::order;
+    use mockito::{mock, Matcher};
+
+    const TRACKED: &str = "61e69015-8549-4bfd-b9c3-01e75843f47d";
+    const CLOSED: &str = "9bd4ba4b-d7cf-4c43-8c09-1b5a7e5f4a5d";
+
+    fn position(symbol: &str, qty: i64) -> Position {
+        Position {
+            symbol: symbol.into(),
+            qty: Decimal::new(qty, 0),
+            avg_entry_price: Decimal::new(100, 0),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconcile() {
+        let _orders = mock("GET", "/orders")
+            .match_header("apca-api-key-id", "APCA_API_KEY_ID")
+            .match_header("apca-api-secret-key", "APCA_API_SECRET_KEY")
+            .match_query(Matcher::UrlEncoded("status".into(), "open".into()))
+            .with_body(format!(
+                "[{}]",
+                ORDER.replace(r#""status": "new""#, r#""status": "partially_filled""#)
+            ))
+            .create();
+        let _closed = mock("GET", format!("/orders/{}", CLOSED).as_str())
+            .match_query(Matcher::UrlEncoded("nested".into(), "false".into()))
+            .with_body(
+                ORDER
+                    .replace(TRACKED, CLOSED)
+                    .replace(r#""status": "new""#, r#""status": "canceled""#),
+            )
+            .create();
+        let _positions = mock("GET", "/positions").with_body(POSITIONS).create();
+        let _account = mock("GET", "/account").with_body(ACCOUNT).create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let tracker = OrderTracker::from_orders(vec![
+            order(TRACKED, "a", "AAPL", OrderStatus::New),
+            order(CLOSED, "b", "AAPL", OrderStatus::New),
+        ]);
+        let book = PositionBook::from_positions(vec![position("AAPL", 5), position("MSFT", 2)]);
+        let discrepancies = Reconciler::new(&client)
+            .reconcile(&tracker, &book, Some(Decimal::new(1000, 0)))
+            .await
+            .unwrap();
+
+        assert_eq!(discrepancies.len(), 4);
+        assert!(matches!(
+            &discrepancies[0],
+            Discrepancy::StatusMismatch { local: OrderStatus::New, order }
+                if order.status == OrderStatus::PartiallyFilled
+        ));
+        assert!(matches!(
+            &discrepancies[1],
+            Discrepancy::StatusMismatch { order, .. } if order.status == OrderStatus::Canceled
+        ));
+        assert_eq!(
+            discrepancies[2],
+            Discrepancy::QtyMismatch {
+                symbol: "MSFT".into(),
+                local: Decimal::new(2, 0),
+                broker: BookPosition {
+                    symbol: "MSFT".into(),
+                    qty: Decimal::ZERO,
+                    avg_entry_price: Decimal::ZERO,
+                },
+            }
+        );
+        assert_eq!(
+            discrepancies[3],
+            Discrepancy::CashMismatch {
+                local: Decimal::new(1000, 0),
+                broker: Decimal::new(-231402, 1),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_order() {
+        let _orders = mock("GET", "/orders")
+            .match_query(Matcher::UrlEncoded("status".into(), "open".into()))
+            .with_body(format!("[{}]", ORDER))
+            .create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let discrepancies = Reconciler::new(&client)
+            .reconcile_orders(&OrderTracker::new())
+            .await
+            .unwrap();
+        assert!(matches!(
+            &discrepancies[..],
+            [Discrepancy::MissingOrder { order }] if order.id.to_string() == TRACKED
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sync_on_reconnect() {
+        let _orders = mock("GET", "/orders")
+            .match_query(Matcher::UrlEncoded("status".into(), "open".into()))
+            .with_body(format!("[{}]", ORDER))
+            .create();
+        let _positions = mock("GET", "/positions").with_body("[]").create();
+        let url = mockito::server_url();
+        let client = client_with_url(&url, "APCA_API_KEY_ID", "APCA_API_SECRET_KEY");
+
+        let mut tracker = OrderTracker::new();
+        let mut book = PositionBook::new();
+        let events = stream::iter(vec![Ok(ConnectionEvent::Reconnected { attempts: 1 })]);
+        let mut reconciliations = 0;
+        Reconciler::new(&client)
+            .sync(
+                events,
+                &mut tracker,
+                &mut book,
+                Duration::from_secs(3600),
+                |tracker, _, discrepancies| {
+                    reconciliations += 1;
+                    for discrepancy in discrepancies {
+                        if let Discrepancy::MissingOrder { order } = discrepancy {
+                            tracker.track(order);
+                        }
+                    }
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(reconciliations, 1);
+        assert!(tracker.get(&TRACKED.parse().unwrap()).is_some());
+    }
+}
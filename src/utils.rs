@@ -1,4 +1,3 @@
-#[cfg(feature = "rest")]
 use chrono::NaiveTime;
 use serde::de::{self, Deserialize, Deserializer};
 use serde::ser::Serializer;
@@ -7,7 +6,6 @@ use std::fmt::Display;
 use std::str::FromStr;
 use tracing::warn;
 
-#[cfg(feature = "rest")]
 pub fn hm_from_str<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
 where
     D: Deserializer<'de>,
@@ -16,7 +14,6 @@ where
     NaiveTime::parse_from_str(&s, "%H:%M").map_err(de::Error::custom)
 }
 
-#[cfg(feature = "rest")]
 pub fn hm_to_string<S>(value: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -72,3 +69,20 @@ where
         Err(_) => Ok(None),
     }
 }
+
+pub fn to_comma_separated<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    let joined: Vec<String> = values.iter().map(ToString::to_string).collect();
+    serializer.collect_str(&joined.join(","))
+}
+
+pub fn null_as_default<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Default + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
+}